@@ -0,0 +1,41 @@
+use serde_derive::{Deserialize, Serialize};
+
+/// Entry of the alarm history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEvent {
+    /// Unix time in seconds.
+    pub timestamp: i64,
+    pub alarm: String,
+    pub metric: String,
+    #[serde(default)]
+    pub dimension: Option<String>,
+    pub value: f64,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum EventKind {
+    /// Alarm status changed, e.g. from `Ok` to `Alarm`.
+    #[serde(rename = "transition")]
+    Transition {
+        from: String,
+        to: String,
+        incident: Option<String>,
+    },
+    /// Notification handed to a sender, with the error when it failed.
+    #[serde(rename = "delivery")]
+    Delivery {
+        sender: String,
+        delivered: bool,
+        attempt: u32,
+        error: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub enum HistoryCommands {
+    Record(HistoryEvent),
+    Stop,
+}
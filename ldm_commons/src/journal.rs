@@ -0,0 +1,56 @@
+use serde_derive::Deserialize;
+use std::io;
+use std::os::unix::net::UnixDatagram;
+
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct JournalConfiguration {
+    #[serde(default = "default_identifier")]
+    pub identifier: String,
+}
+
+fn default_identifier() -> String {
+    String::from("ldm")
+}
+
+/// Writes entries to the systemd journal using its native datagram protocol.
+#[derive(Debug)]
+pub struct JournalWriter {
+    socket: UnixDatagram,
+    identifier: String,
+}
+
+impl JournalWriter {
+    pub fn new(conf: &JournalConfiguration) -> io::Result<JournalWriter> {
+        Ok(JournalWriter {
+            socket: UnixDatagram::unbound()?,
+            identifier: conf.identifier.clone(),
+        })
+    }
+
+    /// `priority` uses syslog severity levels, `fields` names must be upper case.
+    pub fn write(&self, priority: u8, message: &str, fields: &[(&str, String)]) -> io::Result<()> {
+        let mut payload = Vec::new();
+        append_field(&mut payload, "MESSAGE", message);
+        append_field(&mut payload, "PRIORITY", &priority.to_string());
+        append_field(&mut payload, "SYSLOG_IDENTIFIER", &self.identifier);
+        for (name, value) in fields {
+            append_field(&mut payload, name, value);
+        }
+        self.socket.send_to(&payload, JOURNAL_SOCKET)?;
+        Ok(())
+    }
+}
+
+fn append_field(payload: &mut Vec<u8>, name: &str, value: &str) {
+    payload.extend_from_slice(name.as_bytes());
+    if value.contains('\n') {
+        payload.push(b'\n');
+        payload.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        payload.push(b'=');
+    }
+    payload.extend_from_slice(value.as_bytes());
+    payload.push(b'\n');
+}
@@ -1,35 +1,197 @@
-#[derive(Debug, Clone)]
+pub mod history;
+pub mod journal;
+pub mod syslog;
+pub mod template;
+
+use serde::Deserializer;
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Notification {
     pub message: String,
-    pub priority: String,
+    #[serde(alias = "priority")]
+    pub severity: AlarmSeverity,
     pub description: String,
+    pub metric: String,
+    pub value: f64,
+    /// Ids of the senders this notification goes to, empty means default routing.
+    #[serde(default)]
+    pub routes: Vec<String>,
+    #[serde(default)]
+    pub context: AlarmContext,
+}
+
+/// Severity of an alarm, ordered from `Info` to `Critical`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub enum AlarmSeverity {
+    #[serde(rename = "info")]
+    Info,
+    #[serde(rename = "low")]
+    Low,
+    #[serde(rename = "warning")]
+    Warning,
+    #[serde(rename = "high")]
+    High,
+    #[serde(rename = "critical")]
+    Critical,
+}
+
+impl AlarmSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlarmSeverity::Info => "info",
+            AlarmSeverity::Low => "low",
+            AlarmSeverity::Warning => "warning",
+            AlarmSeverity::High => "high",
+            AlarmSeverity::Critical => "critical",
+        }
+    }
+
+    pub fn get_name(&self) -> String {
+        String::from(self.as_str())
+    }
+}
+
+impl FromStr for AlarmSeverity {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "info" => Ok(AlarmSeverity::Info),
+            "low" => Ok(AlarmSeverity::Low),
+            "warning" => Ok(AlarmSeverity::Warning),
+            "high" => Ok(AlarmSeverity::High),
+            "critical" => Ok(AlarmSeverity::Critical),
+            _ => Err(format!(
+                "Unknown severity {}, expected critical, high, warning, low or info",
+                name
+            )),
+        }
+    }
+}
+
+/// Parsed from the name, so severities also work as keys of toml tables.
+impl<'de> serde::Deserialize<'de> for AlarmSeverity {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <String as serde::Deserialize>::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl fmt::Display for AlarmSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct Device {
+    pub name: String,
+    pub ip: String,
+}
+
+/// Details of the alarm evaluation that produced a notification.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AlarmContext {
+    pub alarm: String,
+    pub device: Device,
+    pub dimension: Option<String>,
+    /// Result of the first condition's calculation over `samples`.
+    pub computed: Option<f64>,
+    pub threshold: Option<f64>,
+    pub comparison: Option<String>,
+    pub method: Option<String>,
+    pub samples: Vec<f64>,
+    /// Unix time in seconds.
+    pub timestamp: i64,
+    /// Id shared by the notifications of one firing, until the alarm resolves.
+    #[serde(default)]
+    pub incident: Option<String>,
 }
 
 impl Notification {
-    pub fn new(message: String, priority: String, description: String) -> Notification {
+    pub fn new(
+        message: String,
+        severity: AlarmSeverity,
+        description: String,
+        metric: String,
+        value: f64,
+    ) -> Notification {
         Notification {
             message,
-            priority,
+            severity,
             description,
+            metric,
+            value,
+            routes: Vec::new(),
+            context: AlarmContext::default(),
+        }
+    }
+
+    pub fn routes(mut self, routes: Vec<String>) -> Notification {
+        self.routes = routes;
+        self
+    }
+
+    pub fn context(mut self, context: AlarmContext) -> Notification {
+        self.context = context;
+        self
+    }
+
+    /// Name of the alarm, stays the same when the title is templated.
+    pub fn alarm_name(&self) -> &str {
+        if self.context.alarm.is_empty() {
+            &self.message
+        } else {
+            &self.context.alarm
         }
     }
 }
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
 pub enum AlarmSenderCommands {
     Send(Notification),
+    /// Alarm acknowledged locally, forwarded to the senders keeping alert state.
+    Acknowledge {
+        notification: Notification,
+        comment: String,
+    },
     Stop,
 }
 
-#[derive(Debug, Clone)]
+/// Sample of a metric series, also the line format of recorded samples.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetricData {
+    /// Unix time in seconds.
+    timestamp: i64,
+    #[serde(rename = "metric")]
     name: String,
     value: f64,
 }
 
 impl MetricData {
-    pub fn new(name: String, value: f64) -> MetricData {
-        MetricData { name, value }
+    pub fn new(name: String, value: f64, timestamp: i64) -> MetricData {
+        MetricData {
+            timestamp,
+            name,
+            value,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
     }
 }
 
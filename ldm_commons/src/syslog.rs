@@ -0,0 +1,177 @@
+use chrono::{SecondsFormat, Utc};
+use serde_derive::Deserialize;
+use std::io;
+use std::net::UdpSocket;
+use std::os::unix::net::UnixDatagram;
+
+/// Structured data id used for the ldm parameters, 32473 is the example enterprise number.
+const SD_ID: &str = "ldm@32473";
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SyslogConfiguration {
+    #[serde(default = "default_transport")]
+    pub transport: SyslogTransport,
+    /// Socket path for `unix` transport, `host:port` for `udp` transport.
+    #[serde(default = "default_address")]
+    pub address: String,
+    #[serde(default = "default_facility")]
+    pub facility: Facility,
+    #[serde(default = "default_app_name")]
+    pub app_name: String,
+}
+
+fn default_transport() -> SyslogTransport {
+    SyslogTransport::Unix
+}
+
+fn default_address() -> String {
+    String::from("/dev/log")
+}
+
+fn default_facility() -> Facility {
+    Facility::Daemon
+}
+
+fn default_app_name() -> String {
+    String::from("ldm")
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub enum SyslogTransport {
+    #[serde(rename = "unix")]
+    Unix,
+    #[serde(rename = "udp")]
+    Udp,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub enum Facility {
+    #[serde(rename = "user")]
+    User = 1,
+    #[serde(rename = "daemon")]
+    Daemon = 3,
+    #[serde(rename = "local0")]
+    Local0 = 16,
+    #[serde(rename = "local1")]
+    Local1 = 17,
+    #[serde(rename = "local2")]
+    Local2 = 18,
+    #[serde(rename = "local3")]
+    Local3 = 19,
+    #[serde(rename = "local4")]
+    Local4 = 20,
+    #[serde(rename = "local5")]
+    Local5 = 21,
+    #[serde(rename = "local6")]
+    Local6 = 22,
+    #[serde(rename = "local7")]
+    Local7 = 23,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub enum Severity {
+    #[serde(rename = "critical")]
+    Critical = 2,
+    #[serde(rename = "error")]
+    Error = 3,
+    #[serde(rename = "warning")]
+    Warning = 4,
+    #[serde(rename = "notice")]
+    Notice = 5,
+    #[serde(rename = "info")]
+    Info = 6,
+}
+
+#[derive(Debug)]
+enum Socket {
+    Unix(UnixDatagram),
+    Udp(UdpSocket),
+}
+
+/// Writes RFC 5424 messages to a local syslog socket or a remote UDP collector.
+#[derive(Debug)]
+pub struct SyslogWriter {
+    socket: Socket,
+    address: String,
+    facility: Facility,
+    app_name: String,
+    hostname: String,
+}
+
+impl SyslogWriter {
+    pub fn new(conf: &SyslogConfiguration) -> io::Result<SyslogWriter> {
+        let socket = match conf.transport {
+            SyslogTransport::Unix => Socket::Unix(UnixDatagram::unbound()?),
+            SyslogTransport::Udp => Socket::Udp(UdpSocket::bind("0.0.0.0:0")?),
+        };
+        Ok(SyslogWriter {
+            socket,
+            address: conf.address.clone(),
+            facility: conf.facility,
+            app_name: conf.app_name.clone(),
+            hostname: hostname(),
+        })
+    }
+
+    pub fn write(
+        &self,
+        severity: Severity,
+        msg_id: &str,
+        params: &[(&str, String)],
+        message: &str,
+    ) -> io::Result<()> {
+        let line = self.format(severity, msg_id, params, message);
+        match &self.socket {
+            Socket::Unix(socket) => socket.send_to(line.as_bytes(), &self.address)?,
+            Socket::Udp(socket) => socket.send_to(line.as_bytes(), &self.address)?,
+        };
+        Ok(())
+    }
+
+    fn format(
+        &self,
+        severity: Severity,
+        msg_id: &str,
+        params: &[(&str, String)],
+        message: &str,
+    ) -> String {
+        let structured_data = if params.is_empty() {
+            String::from("-")
+        } else {
+            format!(
+                "[{} {}]",
+                SD_ID,
+                params
+                    .iter()
+                    .map(|(name, value)| format!("{}=\"{}\"", name, escape(value)))
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            )
+        };
+        format!(
+            "<{}>1 {} {} {} {} {} {} {}",
+            self.facility as u8 * 8 + severity as u8,
+            Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            self.hostname,
+            self.app_name,
+            std::process::id(),
+            msg_id,
+            structured_data,
+            message
+        )
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace(']', "\\]")
+}
+
+fn hostname() -> String {
+    match std::fs::read_to_string("/proc/sys/kernel/hostname") {
+        Ok(name) if !name.trim().is_empty() => String::from(name.trim()),
+        _ => String::from("-"),
+    }
+}
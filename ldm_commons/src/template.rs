@@ -0,0 +1,154 @@
+use crate::Notification;
+use chrono::{Local, TimeZone};
+use serde_derive::Deserialize;
+
+/// Title and body templates, placeholders are written as `{{name}}` or `{{name:.1}}` for numbers.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct TemplateConfiguration {
+    pub title: Option<String>,
+    pub body: Option<String>,
+}
+
+impl TemplateConfiguration {
+    /// Renders the templates that are set, `{{title}}` and `{{body}}` refer to the current values.
+    pub fn apply(&self, mut notification: Notification) -> Notification {
+        let title = self
+            .title
+            .as_ref()
+            .map(|template| render(template, &notification));
+        let body = self
+            .body
+            .as_ref()
+            .map(|template| render(template, &notification));
+        if let Some(title) = title {
+            notification.message = title;
+        }
+        if let Some(body) = body {
+            notification.description = body;
+        }
+        notification
+    }
+}
+
+enum Value {
+    Text(String),
+    Number(f64),
+}
+
+pub fn render(template: &str, notification: &Notification) -> String {
+    let mut output = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let tail = &rest[start + 2..];
+        match tail.find("}}") {
+            Some(end) => {
+                let placeholder = &tail[..end];
+                output.push_str(&expand(placeholder, notification));
+                rest = &tail[end + 2..];
+            }
+            None => {
+                output.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+fn expand(placeholder: &str, notification: &Notification) -> String {
+    let mut parts = placeholder.trim().splitn(2, ':');
+    let name = parts.next().unwrap_or("");
+    let precision = parts
+        .next()
+        .and_then(|format| format.trim_start_matches('.').parse::<usize>().ok());
+    match lookup(name, notification) {
+        Some(Value::Number(number)) => format_number(number, precision),
+        Some(Value::Text(text)) => text,
+        None => format!("{{{{{}}}}}", placeholder),
+    }
+}
+
+fn lookup(name: &str, notification: &Notification) -> Option<Value> {
+    let context = &notification.context;
+    Some(match name {
+        "title" => Value::Text(notification.message.clone()),
+        "body" => Value::Text(notification.description.clone()),
+        "alarm" => Value::Text(context.alarm.clone()),
+        "severity" => Value::Text(notification.severity.get_name()),
+        "device" => Value::Text(context.device.name.clone()),
+        "ip" => Value::Text(context.device.ip.clone()),
+        "metric" => Value::Text(notification.metric.clone()),
+        "dimension" => Value::Text(context.dimension.clone().unwrap_or_default()),
+        "value" => Value::Number(notification.value),
+        "computed" => match context.computed {
+            Some(computed) => Value::Number(computed),
+            None => Value::Text(String::new()),
+        },
+        "threshold" => match context.threshold {
+            Some(threshold) => Value::Number(threshold),
+            None => Value::Text(String::new()),
+        },
+        "comparison" => Value::Text(context.comparison.clone().unwrap_or_default()),
+        "method" => Value::Text(context.method.clone().unwrap_or_default()),
+        "samples" => Value::Text(
+            context
+                .samples
+                .iter()
+                .map(|sample| format_number(*sample, None))
+                .collect::<Vec<String>>()
+                .join(", "),
+        ),
+        "timestamp" => Value::Text(match Local.timestamp_opt(context.timestamp, 0).single() {
+            Some(time) => time.to_rfc3339(),
+            None => context.timestamp.to_string(),
+        }),
+        "epoch" => Value::Text(context.timestamp.to_string()),
+        "incident" => Value::Text(context.incident.clone().unwrap_or_default()),
+        _ => return None,
+    })
+}
+
+fn format_number(number: f64, precision: Option<usize>) -> String {
+    match precision {
+        Some(precision) => format!("{:.*}", precision, number),
+        None => {
+            let formatted = format!("{:.2}", number);
+            String::from(formatted.trim_end_matches('0').trim_end_matches('.'))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AlarmContext, AlarmSeverity, Device};
+
+    #[test]
+    fn renders_placeholders() {
+        let notification = Notification::new(
+            String::from("Disk"),
+            AlarmSeverity::High,
+            String::new(),
+            String::from("disk::usage::/var"),
+            93.456,
+        )
+        .context(AlarmContext {
+            device: Device {
+                name: String::from("pi-kitchen"),
+                ip: String::from("10.0.0.2"),
+            },
+            dimension: Some(String::from("/var")),
+            threshold: Some(90.0),
+            ..AlarmContext::default()
+        });
+        assert_eq!(
+            render(
+                "{{device}}: {{dimension}} disk at {{value:.0}}% (threshold {{threshold}}%) {{unknown}}",
+                &notification
+            ),
+            "pi-kitchen: /var disk at 93% (threshold 90%) {{unknown}}"
+        );
+    }
+}
@@ -0,0 +1,299 @@
+use crate::errors::Error;
+use crate::inhibit::{key, Inhibitor};
+use crate::matcher::glob;
+use crate::state::AlarmStates;
+use chrono::Utc;
+use ldm_commons::{AlarmSenderCommands, AlarmSeverity, Notification};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, RwLock};
+
+/// Acknowledges the firing alarms matching both `alarm` and `incident`, at least one is required.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct AckRequest {
+    /// Alarm name, `*` matches any text.
+    pub alarm: Option<String>,
+    /// Incident id of the alarm, as sent in its notifications.
+    pub incident: Option<String>,
+    #[serde(default)]
+    pub comment: String,
+    /// Seconds the acknowledgement lasts, without it the alarm stays acknowledged until it resolves.
+    pub expires: Option<i64>,
+}
+
+impl AckRequest {
+    fn matches(&self, notification: &Notification) -> bool {
+        self.alarm
+            .as_ref()
+            .is_none_or(|alarm| glob(alarm, notification.alarm_name()))
+            && self
+                .incident
+                .as_ref()
+                .is_none_or(|incident| notification.context.incident.as_ref() == Some(incident))
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Acknowledgement {
+    pub alarm: String,
+    pub metric: String,
+    pub dimension: Option<String>,
+    pub incident: Option<String>,
+    pub comment: String,
+    pub acknowledged_at: i64,
+    pub expires_at: Option<i64>,
+}
+
+impl Acknowledgement {
+    fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|end| now >= end)
+    }
+}
+
+/// A firing alarm as listed by the api.
+#[derive(Serialize, Debug, Clone)]
+pub struct FiringAlarm {
+    pub alarm: String,
+    pub metric: String,
+    pub dimension: Option<String>,
+    pub severity: AlarmSeverity,
+    pub value: f64,
+    pub incident: Option<String>,
+    pub acknowledgement: Option<Acknowledgement>,
+}
+
+/// Acknowledged alarms shared between the collector and the api. An acknowledged alarm is
+/// neither notified nor escalated again until it resolves or the acknowledgement expires.
+#[derive(Debug, Clone)]
+pub struct Acknowledgements {
+    acknowledgements: Arc<RwLock<HashMap<String, Acknowledgement>>>,
+    inhibitor: Inhibitor,
+    notification_channel: Sender<AlarmSenderCommands>,
+    state: AlarmStates,
+}
+
+impl Acknowledgements {
+    pub fn new(
+        inhibitor: Inhibitor,
+        notification_channel: Sender<AlarmSenderCommands>,
+    ) -> Acknowledgements {
+        Acknowledgements {
+            acknowledgements: Arc::default(),
+            inhibitor,
+            notification_channel,
+            state: AlarmStates::default(),
+        }
+    }
+
+    /// Restores the acknowledgements saved in `state` and keeps them saved with the alarms.
+    pub fn state(mut self, state: AlarmStates) -> Self {
+        self.acknowledgements
+            .write()
+            .unwrap()
+            .extend(state.acknowledgements());
+        self.state = state;
+        self
+    }
+
+    /// Acknowledges the matching firing alarms and forwards it to their senders, e.g. Opsgenie.
+    /// Returns nothing when no firing alarm matches.
+    pub fn acknowledge(&self, request: AckRequest) -> Result<Vec<Acknowledgement>, Error> {
+        if request.alarm.is_none() && request.incident.is_none() {
+            return Err(Error::Generic(String::from(
+                "An alarm or incident is required",
+            )));
+        }
+        if request.expires.is_some_and(|seconds| seconds <= 0) {
+            return Err(Error::Generic(String::from(
+                "Expiry must be a positive number of seconds",
+            )));
+        }
+        let now = Utc::now().timestamp();
+        let mut acknowledged = Vec::new();
+        for notification in self.inhibitor.firing() {
+            if !request.matches(&notification) {
+                continue;
+            }
+            let acknowledgement = Acknowledgement {
+                alarm: String::from(notification.alarm_name()),
+                metric: notification.metric.clone(),
+                dimension: notification.context.dimension.clone(),
+                incident: notification.context.incident.clone(),
+                comment: request.comment.clone(),
+                acknowledged_at: now,
+                expires_at: request.expires.map(|seconds| now + seconds),
+            };
+            info!(
+                "Alarm {} acknowledged {}",
+                acknowledgement.alarm, acknowledgement.comment
+            );
+            self.acknowledgements
+                .write()
+                .unwrap()
+                .insert(key(&notification), acknowledgement.clone());
+            self.state
+                .acknowledge(&key(&notification), Some(acknowledgement.clone()));
+            if let Err(err) = self
+                .notification_channel
+                .send(AlarmSenderCommands::Acknowledge {
+                    notification,
+                    comment: request.comment.clone(),
+                })
+            {
+                error!("Error while sending to channel {}", err);
+            }
+            acknowledged.push(acknowledgement);
+        }
+        Ok(acknowledged)
+    }
+
+    /// True while the alarm of the notification has an acknowledgement that did not expire.
+    pub fn acknowledged(&self, notification: &Notification) -> bool {
+        let now = Utc::now().timestamp();
+        self.acknowledgements
+            .read()
+            .unwrap()
+            .get(&key(notification))
+            .is_some_and(|acknowledgement| !acknowledgement.is_expired(now))
+    }
+
+    /// Drops the acknowledgement of a resolved alarm, the next time it fires it is notified again.
+    pub fn resolve(&self, key: &str) {
+        self.acknowledgements.write().unwrap().remove(key);
+        self.state.acknowledge(key, None);
+    }
+
+    pub fn firing(&self) -> Vec<FiringAlarm> {
+        let now = Utc::now().timestamp();
+        let mut acknowledgements = self.acknowledgements.write().unwrap();
+        acknowledgements.retain(|_, acknowledgement| !acknowledgement.is_expired(now));
+        self.inhibitor
+            .firing()
+            .into_iter()
+            .map(|notification| FiringAlarm {
+                alarm: String::from(notification.alarm_name()),
+                metric: notification.metric.clone(),
+                dimension: notification.context.dimension.clone(),
+                severity: notification.severity,
+                value: notification.value,
+                incident: notification.context.incident.clone(),
+                acknowledgement: acknowledgements.get(&key(&notification)).cloned(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ldm_commons::AlarmContext;
+    use std::sync::mpsc::{channel, Receiver};
+
+    fn notification(alarm: &str, incident: &str) -> Notification {
+        Notification::new(
+            String::from(alarm),
+            AlarmSeverity::High,
+            String::from("Alarm data set -> 0.9"),
+            String::from("cpu::usage"),
+            0.9,
+        )
+        .context(AlarmContext {
+            incident: Some(String::from(incident)),
+            ..AlarmContext::default()
+        })
+    }
+
+    fn acknowledgements() -> (Acknowledgements, Receiver<AlarmSenderCommands>) {
+        let (tx, rx) = channel();
+        let inhibitor = Inhibitor::default();
+        inhibitor.fire(&notification("High CPU", "cpu@100"));
+        inhibitor.fire(&notification("High load", "load@100"));
+        (Acknowledgements::new(inhibitor, tx), rx)
+    }
+
+    fn request(alarm: &str) -> AckRequest {
+        AckRequest {
+            alarm: Some(String::from(alarm)),
+            comment: String::from("looking into it"),
+            ..AckRequest::default()
+        }
+    }
+
+    #[test]
+    fn matching_alarms_are_acknowledged_on_their_senders() {
+        let (acknowledgements, rx) = acknowledgements();
+        let acknowledged = acknowledgements.acknowledge(request("High C*")).unwrap();
+        assert_eq!(acknowledged.len(), 1);
+        assert_eq!(acknowledged[0].incident.as_deref(), Some("cpu@100"));
+        assert!(acknowledgements.acknowledged(&notification("High CPU", "cpu@100")));
+        assert!(!acknowledgements.acknowledged(&notification("High load", "load@100")));
+        match rx.try_recv() {
+            Ok(AlarmSenderCommands::Acknowledge {
+                notification,
+                comment,
+            }) => {
+                assert_eq!(notification.alarm_name(), "High CPU");
+                assert_eq!(comment, "looking into it");
+            }
+            other => panic!("expected an acknowledgement, got {:?}", other),
+        }
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn invalid_requests_are_rejected() {
+        let (acknowledgements, _rx) = acknowledgements();
+        assert!(acknowledgements.acknowledge(AckRequest::default()).is_err());
+        let expired = AckRequest {
+            expires: Some(0),
+            ..request("High CPU")
+        };
+        assert!(acknowledgements.acknowledge(expired).is_err());
+        assert!(acknowledgements
+            .acknowledge(request("Disk*"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn acknowledgements_expire() {
+        let (acknowledgements, _rx) = acknowledgements();
+        let firing = notification("High CPU", "cpu@100");
+        let acknowledged = acknowledgements
+            .acknowledge(AckRequest {
+                expires: Some(60),
+                ..request("High CPU")
+            })
+            .unwrap();
+        assert_eq!(
+            acknowledged[0].expires_at,
+            Some(acknowledged[0].acknowledged_at + 60)
+        );
+        assert!(acknowledgements.acknowledged(&firing));
+
+        if let Some(acknowledgement) = acknowledgements
+            .acknowledgements
+            .write()
+            .unwrap()
+            .get_mut(&key(&firing))
+        {
+            acknowledgement.expires_at = Some(Utc::now().timestamp());
+        }
+        assert!(!acknowledgements.acknowledged(&firing));
+        // Listing the firing alarms drops the expired acknowledgement.
+        assert!(acknowledgements
+            .firing()
+            .iter()
+            .all(|alarm| alarm.acknowledgement.is_none()));
+    }
+
+    #[test]
+    fn resolved_alarms_lose_their_acknowledgement() {
+        let (acknowledgements, _rx) = acknowledgements();
+        let firing = notification("High CPU", "cpu@100");
+        acknowledgements.acknowledge(request("High CPU")).unwrap();
+        acknowledgements.resolve(&key(&firing));
+        assert!(!acknowledgements.acknowledged(&firing));
+    }
+}
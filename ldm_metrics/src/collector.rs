@@ -1,17 +1,31 @@
-use crate::core::config::{AlarmStatus, Metric, MetricConfiguration};
+use crate::core::config::{Alarm, AlarmStatus, Metric, MetricConfiguration, MetricKind};
 use crate::cpu::metric::CpuUsageMetric;
 use crate::disk::metric::DiskUsageMetric;
 use crate::temp::metric::TemperatureMetric;
+use chrono::{DateTime, Local, TimeZone, Utc};
 use clokwerk::{Interval, Scheduler};
-use ldm_commons::{AlarmSenderCommands, MetricConsumerCommands, MetricData, Notification};
-use std::sync::mpsc::{Receiver, Sender};
+use ldm_commons::history::{EventKind, HistoryCommands, HistoryEvent};
+use ldm_commons::{
+    AlarmContext, AlarmSenderCommands, Device, MetricConsumerCommands, MetricData, Notification,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
 use std::time::Duration;
 
+use crate::ack::Acknowledgements;
 use crate::errors;
+use crate::escalation::{EscalationCommands, Escalator};
+use crate::inhibit::{alarm_key, Inhibitor};
 use crate::mem::metric::MemoryUsageMetric;
 use crate::network::metric::{
     NetworkRxTotalMetric, NetworkRxUsageMetric, NetworkTxTotalMetric, NetworkTxUsageMetric,
 };
+use crate::rules::{CompositeAlarmConfiguration, RuleEngine, RuleEngineCommands};
+use crate::silence::Silences;
+use crate::state::AlarmStates;
+use crate::synthetic::metric::SyntheticMetric;
 use errors::*;
 use std::borrow::BorrowMut;
 
@@ -21,11 +35,82 @@ pub enum IncomingMessage {
     Stop,
 }
 
-#[derive(Debug)]
-pub struct MetricCollector {
+/// Time of the last successful metric poll, shared with the ones watching the collector.
+#[derive(Debug, Clone, Default)]
+pub struct CollectorHealth {
+    last_collected: Arc<AtomicI64>,
+}
+
+impl CollectorHealth {
+    fn mark(&self) {
+        self.last_collected
+            .store(Utc::now().timestamp(), Ordering::SeqCst);
+    }
+
+    /// True when a metric was polled within the last `seconds`.
+    pub fn is_healthy(&self, seconds: i64) -> bool {
+        let last = self.last_collected.load(Ordering::SeqCst);
+        last > 0 && Utc::now().timestamp() - last <= seconds
+    }
+}
+
+/// Channels and shared state used by every scheduled `collect`.
+#[derive(Debug, Clone)]
+pub(crate) struct CollectContext {
     notification_channel: Sender<AlarmSenderCommands>,
     metric_channel: Sender<MetricConsumerCommands>,
+    pub(crate) device: Device,
+    health: CollectorHealth,
+    silences: Silences,
+    pub(crate) inhibitor: Inhibitor,
+    pub(crate) acknowledgements: Acknowledgements,
+    rule_channel: Option<Sender<RuleEngineCommands>>,
+    pub(crate) state: AlarmStates,
+    history: Option<Sender<HistoryCommands>>,
+    escalation_channel: Option<Sender<EscalationCommands>>,
+}
+
+impl CollectContext {
+    fn escalate(&self, command: EscalationCommands) {
+        if let Some(escalation) = &self.escalation_channel {
+            let _ = escalation.send(command);
+        }
+    }
+
+    pub(crate) fn record(&self, event: HistoryEvent) {
+        if let Some(history) = &self.history {
+            if let Err(err) = history.send(HistoryCommands::Record(event)) {
+                error!("Error while sending to history channel {}", err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+impl CollectContext {
+    /// Context of a collector without composites, escalation or history.
+    pub(crate) fn for_tests(notification_channel: Sender<AlarmSenderCommands>) -> CollectContext {
+        let (metric_channel, _) = channel();
+        MetricCollector::new(
+            notification_channel,
+            metric_channel,
+            Vec::new(),
+            Vec::new(),
+            Device::default(),
+            Silences::default(),
+            Inhibitor::default(),
+        )
+        .context
+    }
+}
+
+#[derive(Debug)]
+pub struct MetricCollector {
     configurations: Vec<MetricConfiguration>,
+    composites: Vec<CompositeAlarmConfiguration>,
+    context: CollectContext,
+    escalation_tx: Sender<EscalationCommands>,
+    escalation_rx: Option<Receiver<EscalationCommands>>,
 }
 
 impl MetricCollector {
@@ -33,24 +118,77 @@ impl MetricCollector {
         notification_channel: Sender<AlarmSenderCommands>,
         metric_channel: Sender<MetricConsumerCommands>,
         configurations: Vec<MetricConfiguration>,
+        composites: Vec<CompositeAlarmConfiguration>,
+        device: Device,
+        silences: Silences,
+        inhibitor: Inhibitor,
     ) -> MetricCollector {
+        let (escalation_tx, escalation_rx) = channel();
+        let acknowledgements =
+            Acknowledgements::new(inhibitor.clone(), notification_channel.clone());
         MetricCollector {
-            notification_channel,
-            metric_channel,
             configurations,
+            composites,
+            context: CollectContext {
+                notification_channel,
+                metric_channel,
+                device,
+                health: CollectorHealth::default(),
+                silences,
+                inhibitor,
+                acknowledgements,
+                rule_channel: None,
+                state: AlarmStates::default(),
+                history: None,
+                escalation_channel: None,
+            },
+            escalation_tx,
+            escalation_rx: Some(escalation_rx),
         }
     }
 
+    /// Restores alarms from `state` on start and keeps it updated after every poll, it is
+    /// written when an alarm changes status, every `interval` seconds and on stop.
+    pub fn state(mut self, state: AlarmStates) -> Self {
+        self.context.acknowledgements = self.context.acknowledgements.state(state.clone());
+        self.context.state = state;
+        self
+    }
+
+    pub fn health(&self) -> CollectorHealth {
+        self.context.health.clone()
+    }
+
+    /// Acknowledgements of the firing alarms, e.g. for the api.
+    pub fn acknowledgements(&self) -> Acknowledgements {
+        self.context.acknowledgements.clone()
+    }
+
     pub fn start(&mut self, rx: Receiver<IncomingMessage>) {
+        if !self.composites.is_empty() {
+            let (rule_tx, rule_rx) = channel();
+            let mut engine = RuleEngine::new(self.composites.clone(), self.context.clone());
+            std::thread::spawn(move || engine.start(rule_rx));
+            self.context.rule_channel = Some(rule_tx);
+        }
+        let escalates = self
+            .configurations
+            .iter()
+            .flat_map(|config| config.alarms.iter())
+            .any(|alarm| !alarm.escalation().is_empty());
+        if let (true, Some(escalation_rx)) = (escalates, self.escalation_rx.take()) {
+            let mut escalator = Escalator::new(self.context.clone());
+            std::thread::spawn(move || escalator.start(escalation_rx));
+            self.context.escalation_channel = Some(self.escalation_tx.clone());
+        }
         let metrics = self.setup_metrics();
         let mut scheduler = Scheduler::new();
         for mut metric in metrics {
             info!("{:?}", metric);
-            let sender = self.notification_channel.clone();
-            let metric_channel = self.metric_channel.clone();
+            let context = self.context.clone();
             scheduler
                 .every(Interval::Seconds(metric.get_period()))
-                .run(move || collect(metric.borrow_mut(), &sender, &metric_channel));
+                .run(move || collect(metric.borrow_mut(), &context));
         }
         let handle = scheduler.watch_thread(Duration::from_secs(5));
         loop {
@@ -60,6 +198,13 @@ impl MetricCollector {
                     IncomingMessage::Stop => {
                         info!("Stopping Collector");
                         handle.stop();
+                        if let Some(rules) = &self.context.rule_channel {
+                            let _ = rules.send(RuleEngineCommands::Stop);
+                        }
+                        if let Some(escalation) = &self.context.escalation_channel {
+                            let _ = escalation.send(EscalationCommands::Stop);
+                        }
+                        self.context.state.persist();
                         break;
                     }
                 },
@@ -76,8 +221,9 @@ impl MetricCollector {
         let mut metrics: Vec<Box<dyn Metric>> = Vec::new();
         for conf in &self.configurations {
             match self.setup_metric(conf) {
-                Ok(alarm) => {
-                    metrics.push(alarm);
+                Ok(mut metric) => {
+                    self.restore(metric.as_mut());
+                    metrics.push(metric);
                 }
                 Err(err) => error!("{}", err),
             }
@@ -85,7 +231,80 @@ impl MetricCollector {
         return metrics;
     }
 
+    /// Feeds recorded samples through the alarms in timestamp order, with the sample time as
+    /// the clock. Composite alarms and escalations are evaluated in step with the samples.
+    /// Returns the number of samples that belong to a configured metric.
+    pub fn replay(&mut self, mut samples: Vec<MetricData>) -> usize {
+        samples.sort_by_key(MetricData::timestamp);
+        let (rule_tx, rule_rx) = channel();
+        let mut engine = RuleEngine::new(self.composites.clone(), self.context.clone());
+        self.context.rule_channel = Some(rule_tx);
+        let (escalation_tx, escalation_rx) = channel();
+        let mut escalator = Escalator::new(self.context.clone());
+        self.context.escalation_channel = Some(escalation_tx);
+        let mut metrics: HashMap<String, Box<dyn Metric>> = self
+            .setup_metrics()
+            .into_iter()
+            .map(|metric| (metric.get_name(), metric))
+            .collect();
+        let mut unknown = HashSet::new();
+        let mut replayed = 0;
+        for sample in &samples {
+            let metric = match metrics.get_mut(sample.name()) {
+                Some(metric) => metric,
+                None => {
+                    if unknown.insert(sample.name()) {
+                        warn!(
+                            "Skipping samples of {}, it is not configured",
+                            sample.name()
+                        );
+                    }
+                    continue;
+                }
+            };
+            let now = match Local.timestamp_opt(sample.timestamp(), 0).single() {
+                Some(now) => now,
+                None => continue,
+            };
+            evaluate(metric.as_mut(), sample.value(), &now, &self.context);
+            for command in rule_rx.try_iter() {
+                engine.record(command);
+            }
+            engine.evaluate(sample.timestamp());
+            for command in escalation_rx.try_iter() {
+                escalator.record(command);
+            }
+            escalator.escalate(sample.timestamp());
+            replayed += 1;
+        }
+        replayed
+    }
+
+    /// Alarm transitions are sent to `history`.
+    pub fn history(mut self, history: Sender<HistoryCommands>) -> Self {
+        self.context.history = Some(history);
+        self
+    }
+
+    fn restore(&self, metric: &mut dyn Metric) {
+        let name = metric.get_name();
+        let dimension = metric.get_dimension();
+        for alarm in metric.get_alarms() {
+            let key = alarm_key(&name, dimension.as_deref(), &alarm.config.name());
+            if self.context.state.restore(&key, alarm) {
+                info!(
+                    "Alarm {} restored as {:?}",
+                    alarm.config.name(),
+                    alarm.previous_status
+                );
+            }
+        }
+    }
+
     fn setup_metric(&self, configuration: &MetricConfiguration) -> Result<Box<dyn Metric>, Error> {
+        if configuration.kind == MetricKind::Synthetic {
+            return Ok(Box::new(SyntheticMetric::new(configuration.clone())?));
+        }
         match configuration.name.as_str() {
             "cpu::usage" => Ok(Box::new(CpuUsageMetric::new(configuration.clone()))),
             "memory::usage" => Ok(Box::new(MemoryUsageMetric::new(configuration.clone()))),
@@ -109,62 +328,329 @@ impl MetricCollector {
     }
 }
 
-fn collect(
-    metric: &mut Box<dyn Metric>,
-    tx: &Sender<AlarmSenderCommands>,
-    metric_tx: &Sender<MetricConsumerCommands>,
-) {
+fn collect(metric: &mut Box<dyn Metric>, context: &CollectContext) {
     match metric.poll_metric() {
         Ok(data) => {
-            metric_tx.send(MetricConsumerCommands::Send(MetricData::new(
-                metric.get_name(),
-                data,
-            )));
-            for alarm in metric.get_alarms() {
-                match alarm.check(data) {
-                    Err(err) => error!("Error occurred: {}", err),
-                    Ok(result) => {
-                        match result {
-                            AlarmStatus::Ok => match alarm.previous_status {
-                                AlarmStatus::Ok => {
-                                    debug!("No change");
-                                }
-                                AlarmStatus::Alarm => {
-                                    debug!("State changed from Alarm to Ok");
-                                }
-                                AlarmStatus::NoData => {
-                                    debug!("State changed from NoData to Ok");
-                                }
-                            },
+            context.health.mark();
+            evaluate(metric.as_mut(), data, &Local::now(), context);
+        }
+        Err(err) => {
+            error!("{}", err);
+            release(metric.as_mut(), context);
+        }
+    }
+}
+
+/// Without a sample the alarms of the metric are not known to fire, so they stop inhibiting.
+fn release(metric: &mut dyn Metric, context: &CollectContext) {
+    let name = metric.get_name();
+    let dimension = metric.get_dimension();
+    for alarm in metric.get_alarms() {
+        let key = alarm_key(&name, dimension.as_deref(), &alarm.config.name());
+        context.inhibitor.resolve(&key);
+    }
+}
+
+/// Checks the alarms of the metric against a sample taken at `now` and notifies the firing ones.
+fn evaluate(metric: &mut dyn Metric, data: f64, now: &DateTime<Local>, context: &CollectContext) {
+    let timestamp = now.timestamp();
+    let name = metric.get_name();
+    let dimension = metric.get_dimension();
+    if let Err(err) = context
+        .metric_channel
+        .send(MetricConsumerCommands::Send(MetricData::new(
+            name.clone(),
+            data,
+            timestamp,
+        )))
+    {
+        error!("Error while sending to metric channel {}", err);
+    }
+    if let Some(rules) = &context.rule_channel {
+        let _ = rules.send(RuleEngineCommands::Sample {
+            metric: name.clone(),
+            value: data,
+            timestamp,
+        });
+    }
+    let mut firing = Vec::new();
+    for alarm in metric.get_alarms() {
+        let key = alarm_key(&name, dimension.as_deref(), &alarm.config.name());
+        let opened = alarm.incident.clone();
+        match alarm.check_at(data, now) {
+            Err(err) => error!("Error occurred: {}", err),
+            Ok(result) => {
+                match result {
+                    AlarmStatus::Ok => {
+                        match alarm.previous_status {
+                            AlarmStatus::Ok => {
+                                debug!("No change");
+                            }
                             AlarmStatus::Alarm => {
-                                let desc = format!(
-                                    "Alarm data set -> {}",
-                                    alarm
-                                        .samples
-                                        .iter()
-                                        .map(ToString::to_string)
-                                        .collect::<Vec<String>>()
-                                        .join(":")
-                                );
-                                if let Err(err) =
-                                    tx.send(AlarmSenderCommands::Send(Notification::new(
-                                        alarm.config.name(),
-                                        alarm.config.severity(),
-                                        desc,
-                                    )))
-                                {
-                                    error!("Error while sending to channel {}", err);
-                                } else {
-                                    info!("State changed to Alarm");
-                                }
+                                debug!("State changed from Alarm to Ok");
+                            }
+                            AlarmStatus::NoData => {
+                                debug!("State changed from NoData to Ok");
+                            }
+                        }
+                        if let Some(incident) = alarm.incident.take() {
+                            info!("Incident {} resolved", incident);
+                        }
+                        context.inhibitor.resolve(&key);
+                        context.acknowledgements.resolve(&key);
+                        if !alarm.config.escalation().is_empty() {
+                            context.escalate(EscalationCommands::Resolve { key: key.clone() });
+                        }
+                    }
+                    AlarmStatus::Alarm => {
+                        if alarm.incident.is_none() {
+                            alarm.incident = Some(incident_id(&key, timestamp));
+                        }
+                        let mut desc = format!(
+                            "Alarm data set -> {}",
+                            alarm
+                                .samples
+                                .iter()
+                                .map(ToString::to_string)
+                                .collect::<Vec<String>>()
+                                .join(":")
+                        );
+                        for evaluation in &alarm.evaluations {
+                            if let Some(description) = &evaluation.description {
+                                desc.push_str(&format!(", {}", description));
                             }
-                            AlarmStatus::NoData => {}
                         }
-                        alarm.set_status(result);
+                        let notification = build_notification(
+                            alarm,
+                            desc,
+                            &name,
+                            dimension.clone(),
+                            data,
+                            timestamp,
+                            &context.device,
+                        );
+                        context.inhibitor.fire(&notification);
+                        if !alarm.config.escalation().is_empty() {
+                            context.escalate(EscalationCommands::Fire {
+                                key: key.clone(),
+                                notification: notification.clone(),
+                                steps: alarm.config.escalation().to_vec(),
+                            });
+                        }
+                        firing.push(notification);
                     }
+                    AlarmStatus::NoData => context.inhibitor.resolve(&key),
                 }
+                if let Some(rules) = &context.rule_channel {
+                    let _ = rules.send(RuleEngineCommands::AlarmState {
+                        alarm: alarm.config.name(),
+                        firing: matches!(result, AlarmStatus::Alarm),
+                    });
+                }
+                if result != alarm.previous_status {
+                    context.record(HistoryEvent {
+                        timestamp,
+                        alarm: alarm.config.name(),
+                        metric: name.clone(),
+                        dimension: dimension.clone(),
+                        value: data,
+                        kind: EventKind::Transition {
+                            from: format!("{:?}", alarm.previous_status),
+                            to: format!("{:?}", result),
+                            incident: alarm.incident.clone().or(opened),
+                        },
+                    });
+                }
+                alarm.set_status(result);
+            }
+        }
+        context.state.record(&key, alarm);
+    }
+    context.state.persist_changes();
+    // Sent once every alarm of the metric is evaluated, so rules between
+    // them do not depend on their order.
+    for notification in firing {
+        notify(notification, context);
+    }
+}
+
+fn incident_id(key: &str, timestamp: i64) -> String {
+    format!("{}@{}", key, timestamp)
+}
+
+/// Time the incident opened, read back from its id, also for incidents restored on start.
+pub(crate) fn incident_start(incident: &str) -> Option<i64> {
+    incident.rsplit('@').next()?.parse().ok()
+}
+
+/// Silences are matched at the time of the notification, so replayed samples see the
+/// silences of their own time.
+pub(crate) fn notify(notification: Notification, context: &CollectContext) {
+    let now = Local
+        .timestamp_opt(notification.context.timestamp, 0)
+        .single()
+        .unwrap_or_else(Local::now);
+    if let Some(silence) = context.silences.silenced_at(&notification, now) {
+        info!(
+            "State changed to Alarm, {} silenced by {}",
+            notification.alarm_name(),
+            silence
+        );
+    } else if let Some(source) = context.inhibitor.inhibited_by(&notification) {
+        info!(
+            "State changed to Alarm, {} inhibited by {}",
+            notification.alarm_name(),
+            source
+        );
+    } else if context.acknowledgements.acknowledged(&notification) {
+        info!(
+            "State changed to Alarm, {} acknowledged",
+            notification.alarm_name()
+        );
+    } else if let Err(err) = context
+        .notification_channel
+        .send(AlarmSenderCommands::Send(notification))
+    {
+        error!("Error while sending to channel {}", err);
+    } else {
+        info!("State changed to Alarm");
+    }
+}
+
+fn build_notification(
+    alarm: &Alarm,
+    description: String,
+    metric: &str,
+    dimension: Option<String>,
+    value: f64,
+    timestamp: i64,
+    device: &Device,
+) -> Notification {
+    let evaluation = alarm.evaluations.first();
+    let notification = Notification::new(
+        alarm.config.name(),
+        alarm.config.severity(),
+        description,
+        String::from(metric),
+        value,
+    )
+    .routes(alarm.config.notify())
+    .context(AlarmContext {
+        alarm: alarm.config.name(),
+        device: device.clone(),
+        dimension,
+        computed: evaluation.map(|e| e.computed),
+        threshold: evaluation.map(|e| e.threshold),
+        comparison: evaluation.map(|e| e.comparison.clone()),
+        method: evaluation.map(|e| e.method.clone()),
+        samples: alarm.samples.clone(),
+        timestamp,
+        incident: alarm.incident.clone(),
+    });
+    match alarm.config.template() {
+        Some(template) => template.apply(notification),
+        None => notification,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::fixture::FixtureSystem;
+
+    fn disk(fixture: &str) -> Box<dyn Metric> {
+        let config: MetricConfiguration = serde_json::from_value(serde_json::json!({
+            "name": "disk::usage",
+            "dimension": "/",
+            "interval": 60,
+            "alarms": [{
+                "name": "Disk is full",
+                "severity": "high",
+                "sample_size": 1,
+                "conditions": [{"comparison": "ge", "method": "avg", "value": 50.0}],
+            }],
+        }))
+        .unwrap();
+        let system = FixtureSystem::new(format!(
+            "{}/tests/fixtures/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            fixture
+        ));
+        Box::new(DiskUsageMetric::new_with_system(config, Arc::new(system)))
+    }
+
+    #[test]
+    fn failed_poll_stops_inhibiting() {
+        let (notification_tx, _notifications) = channel();
+        let context = CollectContext::for_tests(notification_tx);
+        collect(&mut disk("host"), &context);
+        assert_eq!(context.inhibitor.firing().len(), 1);
+        // The hwmon fixture has no mounts, the poll fails.
+        collect(&mut disk("hwmon"), &context);
+        assert!(context.inhibitor.firing().is_empty());
+    }
+
+    #[test]
+    fn replay_fires_at_the_recorded_times() {
+        let path =
+            std::env::temp_dir().join(format!("ldm_replay_collector_{}.csv", std::process::id()));
+        std::fs::write(
+            &path,
+            "timestamp,metric,value\n\
+             2024-06-03T10:00:00+00:00,cpu::usage,0.5\n\
+             1717408860,cpu::usage,0.9\n\
+             1717408920,cpu::usage,0.95\n\
+             1717408980,memory::usage,0.1\n",
+        )
+        .unwrap();
+        let samples = crate::replay::read_samples(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let config: MetricConfiguration = serde_json::from_value(serde_json::json!({
+            "name": "cpu::usage",
+            "interval": 60,
+            "alarms": [{
+                "name": "High CPU",
+                "severity": "high",
+                "sample_size": 2,
+                "conditions": [{"comparison": "ge", "method": "avg", "value": 0.8}],
+            }],
+        }))
+        .unwrap();
+        let (notification_tx, notifications) = channel();
+        let (metric_tx, metrics) = channel();
+        let mut collector = MetricCollector::new(
+            notification_tx,
+            metric_tx,
+            vec![config],
+            Vec::new(),
+            Device::default(),
+            Silences::default(),
+            Inhibitor::default(),
+        );
+
+        assert_eq!(collector.replay(samples), 3);
+        match notifications.try_recv() {
+            Ok(AlarmSenderCommands::Send(notification)) => {
+                assert_eq!(notification.alarm_name(), "High CPU");
+                assert_eq!(notification.context.timestamp, 1_717_408_920);
+                assert_eq!(
+                    notification.context.incident.as_deref(),
+                    Some("cpu::usage||High CPU@1717408920")
+                );
             }
+            other => panic!("expected a notification, got {:?}", other),
         }
-        Err(err) => error!("{}", err),
+        assert!(notifications.try_recv().is_err());
+        let timestamps: Vec<i64> = metrics
+            .try_iter()
+            .filter_map(|command| match command {
+                MetricConsumerCommands::Send(sample) => Some(sample.timestamp()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            timestamps,
+            vec![1_717_408_800, 1_717_408_860, 1_717_408_920]
+        );
     }
 }
@@ -0,0 +1,330 @@
+use crate::core::config::Evaluation;
+use chrono::{DateTime, Datelike, Local, Timelike};
+use serde::{de, Deserialize as _, Deserializer};
+use serde_derive::Deserialize;
+use std::collections::VecDeque;
+
+const HOURS_OF_WEEK: usize = 7 * 24;
+
+/// Condition holding when the latest sample deviates from what the metric usually
+/// looks like, instead of crossing a fixed threshold.
+#[derive(Deserialize, Debug, Clone)]
+pub struct AnomalyConfiguration {
+    anomaly: AnomalyMethod,
+    /// Deviation, in standard deviations, a sample needs to count as anomalous.
+    #[serde(default = "default_sensitivity", deserialize_with = "positive")]
+    sensitivity: f64,
+    #[serde(default)]
+    direction: Direction,
+    /// Samples learned before the condition can hold, for `baseline` per hour of the week.
+    #[serde(default = "default_warm_up")]
+    warm_up: u64,
+    /// Smoothing factor of `ewma`, above 0 and at most 1, higher follows the metric faster.
+    #[serde(default = "default_alpha", deserialize_with = "fraction")]
+    alpha: f64,
+    /// Samples in the rolling window of `zscore`, at least 2.
+    #[serde(default = "default_window", deserialize_with = "at_least_two")]
+    window: usize,
+}
+
+fn positive<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let sensitivity = f64::deserialize(deserializer)?;
+    if sensitivity <= 0.0 {
+        return Err(de::Error::custom("anomaly sensitivity must be above 0"));
+    }
+    Ok(sensitivity)
+}
+
+fn fraction<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let alpha = f64::deserialize(deserializer)?;
+    if alpha <= 0.0 || alpha > 1.0 {
+        return Err(de::Error::custom(
+            "anomaly alpha must be above 0 and at most 1",
+        ));
+    }
+    Ok(alpha)
+}
+
+/// The deviation of fewer samples is always 0, every sample would be anomalous.
+fn at_least_two<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+    let window = usize::deserialize(deserializer)?;
+    if window < 2 {
+        return Err(de::Error::custom(
+            "anomaly window must be at least 2 samples",
+        ));
+    }
+    Ok(window)
+}
+
+fn default_sensitivity() -> f64 {
+    3.0
+}
+
+fn default_warm_up() -> u64 {
+    30
+}
+
+fn default_alpha() -> f64 {
+    0.1
+}
+
+fn default_window() -> usize {
+    60
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum AnomalyMethod {
+    /// Band around an exponentially weighted moving average and variance.
+    #[serde(rename = "ewma")]
+    Ewma,
+    /// Z-score against the mean of a rolling window.
+    #[serde(rename = "zscore")]
+    ZScore,
+    /// Z-score against the samples seen at the same hour of the week.
+    #[serde(rename = "baseline")]
+    Baseline,
+}
+
+impl AnomalyMethod {
+    pub fn get_name(&self) -> String {
+        String::from(match self {
+            AnomalyMethod::Ewma => "ewma",
+            AnomalyMethod::ZScore => "zscore",
+            AnomalyMethod::Baseline => "baseline",
+        })
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum Direction {
+    #[serde(rename = "above")]
+    Above,
+    #[serde(rename = "below")]
+    Below,
+    #[default]
+    #[serde(rename = "both")]
+    Both,
+}
+
+impl Direction {
+    pub fn get_name(&self) -> String {
+        String::from(match self {
+            Direction::Above => "above",
+            Direction::Below => "below",
+            Direction::Both => "both",
+        })
+    }
+}
+
+/// Running mean and variance (Welford).
+#[derive(Debug, Clone, Default)]
+struct Moments {
+    count: u64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Moments {
+    fn add(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    fn deviation(&self) -> f64 {
+        if self.count < 2 {
+            0.0
+        } else {
+            (self.m2 / (self.count - 1) as f64).sqrt()
+        }
+    }
+}
+
+/// Learned state of an anomaly condition.
+#[derive(Debug, Clone)]
+pub struct AnomalyDetector {
+    config: AnomalyConfiguration,
+    seen: u64,
+    mean: f64,
+    variance: f64,
+    window: VecDeque<f64>,
+    baseline: Vec<Moments>,
+}
+
+fn score(value: f64, mean: f64, deviation: f64) -> f64 {
+    if deviation > 0.0 {
+        (value - mean) / deviation
+    } else if value == mean {
+        0.0
+    } else {
+        (value - mean).signum() * f64::INFINITY
+    }
+}
+
+fn hour_of_week(time: &DateTime<Local>) -> usize {
+    time.weekday().num_days_from_monday() as usize * 24 + time.hour() as usize
+}
+
+impl AnomalyDetector {
+    pub fn new(config: AnomalyConfiguration) -> AnomalyDetector {
+        let baseline = match config.anomaly {
+            AnomalyMethod::Baseline => vec![Moments::default(); HOURS_OF_WEEK],
+            _ => Vec::new(),
+        };
+        AnomalyDetector {
+            config,
+            seen: 0,
+            mean: 0.0,
+            variance: 0.0,
+            window: VecDeque::new(),
+            baseline,
+        }
+    }
+
+    /// Scores `value` against what was learned so far, then learns it.
+    pub fn observe(&mut self, value: f64, time: &DateTime<Local>) -> Evaluation {
+        let (learned, z) = match self.config.anomaly {
+            AnomalyMethod::Ewma => {
+                let z = score(value, self.mean, self.variance.sqrt());
+                if self.seen == 0 {
+                    self.mean = value;
+                } else {
+                    let alpha = self.config.alpha;
+                    let delta = value - self.mean;
+                    self.mean += alpha * delta;
+                    self.variance = (1.0 - alpha) * (self.variance + alpha * delta * delta);
+                }
+                (self.seen, z)
+            }
+            AnomalyMethod::ZScore => {
+                let mut moments = Moments::default();
+                self.window.iter().for_each(|sample| moments.add(*sample));
+                self.window.push_back(value);
+                if self.window.len() > self.config.window {
+                    self.window.pop_front();
+                }
+                (self.seen, score(value, moments.mean, moments.deviation()))
+            }
+            AnomalyMethod::Baseline => {
+                let moments = &mut self.baseline[hour_of_week(time)];
+                let z = score(value, moments.mean, moments.deviation());
+                let learned = moments.count;
+                moments.add(value);
+                (learned, z)
+            }
+        };
+        self.seen += 1;
+        let sensitivity = self.config.sensitivity;
+        let deviates = match self.config.direction {
+            Direction::Above => z > sensitivity,
+            Direction::Below => z < -sensitivity,
+            Direction::Both => z.abs() > sensitivity,
+        };
+        Evaluation {
+            holds: learned >= self.config.warm_up && deviates,
+            computed: z,
+            threshold: sensitivity,
+            comparison: self.config.direction.get_name(),
+            method: self.config.anomaly.get_name(),
+            description: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::Condition;
+    use chrono::{Duration, TimeZone};
+
+    fn detector(config: serde_json::Value) -> AnomalyDetector {
+        AnomalyDetector::new(serde_json::from_value(config).unwrap())
+    }
+
+    fn monday() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 6, 3, 10, 0, 0).unwrap()
+    }
+
+    /// Feeds `count` samples alternating around 10 and returns whether the last one held.
+    fn learn(detector: &mut AnomalyDetector, count: usize, step: Duration) -> bool {
+        let mut holds = false;
+        for i in 0..count {
+            let value = if i % 2 == 0 { 9.0 } else { 11.0 };
+            holds = detector.observe(value, &(monday() + step * i as i32)).holds;
+        }
+        holds
+    }
+
+    #[test]
+    fn invalid_configurations_are_rejected() {
+        for (config, message) in [
+            (
+                serde_json::json!({"anomaly": "zscore", "window": 0}),
+                "window",
+            ),
+            (
+                serde_json::json!({"anomaly": "zscore", "window": 1}),
+                "window",
+            ),
+            (
+                serde_json::json!({"anomaly": "ewma", "alpha": 0.0}),
+                "alpha",
+            ),
+            (
+                serde_json::json!({"anomaly": "ewma", "alpha": 1.5}),
+                "alpha",
+            ),
+            (
+                serde_json::json!({"anomaly": "ewma", "sensitivity": 0.0}),
+                "sensitivity",
+            ),
+        ] {
+            // The error must surface through the condition instead of matching no variant.
+            let err = serde_json::from_value::<Condition>(config).unwrap_err();
+            assert!(err.to_string().contains(message), "{}", err);
+        }
+    }
+
+    #[test]
+    fn ewma_holds_outside_the_band() {
+        let mut detector = detector(serde_json::json!({"anomaly": "ewma", "warm_up": 10}));
+        assert!(!learn(&mut detector, 40, Duration::minutes(1)));
+        assert!(detector.observe(30.0, &monday()).holds);
+    }
+
+    #[test]
+    fn zscore_holds_against_the_window() {
+        let mut detector = detector(serde_json::json!({
+            "anomaly": "zscore",
+            "window": 10,
+            "warm_up": 10,
+            "direction": "above",
+        }));
+        assert!(!learn(&mut detector, 20, Duration::minutes(1)));
+        assert!(!detector.observe(-10.0, &monday()).holds);
+        // Only deviations above the window count.
+        assert!(detector.observe(40.0, &monday()).holds);
+    }
+
+    #[test]
+    fn zscore_does_not_hold_during_warm_up() {
+        let mut detector = detector(serde_json::json!({"anomaly": "zscore", "warm_up": 10}));
+        assert!(!learn(&mut detector, 4, Duration::minutes(1)));
+        assert!(!detector.observe(30.0, &monday()).holds);
+    }
+
+    #[test]
+    fn baseline_learns_per_hour_of_week() {
+        let mut detector = detector(serde_json::json!({"anomaly": "baseline", "warm_up": 4}));
+        assert!(!learn(&mut detector, 4, Duration::weeks(1)));
+        let next_week = monday() + Duration::weeks(4);
+        assert!(detector.observe(30.0, &next_week).holds);
+        // Nothing was learned an hour later yet.
+        assert!(
+            !detector
+                .observe(30.0, &(next_week + Duration::hours(1)))
+                .holds
+        );
+    }
+}
@@ -1,7 +1,15 @@
+use crate::core::anomaly::{AnomalyConfiguration, AnomalyDetector};
+use crate::core::forecast::{ForecastConfiguration, Forecaster};
+use crate::escalation::EscalationStep;
+use crate::synthetic::config::SyntheticConfiguration;
+use chrono::{DateTime, Local};
 use core::fmt;
-use serde_derive::Deserialize;
+use ldm_commons::template::TemplateConfiguration;
+pub use ldm_commons::AlarmSeverity;
+use serde_derive::{Deserialize, Serialize};
 use std::fmt::Debug;
 
+#[derive(Debug)]
 pub struct Error {
     message: String,
 }
@@ -32,6 +40,9 @@ pub type CalculateError = Error;
 
 pub trait Metric: Debug + Send + Sync {
     fn get_name(&self) -> String;
+    fn get_dimension(&self) -> Option<String> {
+        None
+    }
     fn poll_metric(&mut self) -> Result<f64, SampleCollectError>;
     fn get_alarms(&mut self) -> &mut [Alarm];
     fn get_period(&self) -> u32;
@@ -51,14 +62,54 @@ pub struct Alarm {
     pub config: AlarmConfiguration,
     pub samples: Vec<f64>,
     pub previous_status: AlarmStatus,
+    /// Id of the open incident, set when the alarm fires and cleared when it resolves.
+    #[serde(skip)]
+    pub incident: Option<String>,
+    /// Result of each condition on the last check.
+    #[serde(skip)]
+    pub evaluations: Vec<Evaluation>,
+    #[serde(skip)]
+    detectors: Vec<Option<Detector>>,
+}
+
+/// State a condition keeps between samples.
+#[derive(Debug, Clone)]
+enum Detector {
+    Anomaly(AnomalyDetector),
+    Forecast(Forecaster),
+}
+
+impl Detector {
+    fn observe(&mut self, value: f64, now: &DateTime<Local>) -> Evaluation {
+        match self {
+            Detector::Anomaly(detector) => detector.observe(value, now),
+            Detector::Forecast(forecaster) => forecaster.observe(value, now.timestamp()),
+        }
+    }
 }
 
 impl Alarm {
     pub fn new(config: AlarmConfiguration) -> Alarm {
+        let detectors = config
+            .conditions
+            .iter()
+            .map(|condition| match condition {
+                Condition::Anomaly(anomaly) => {
+                    Some(Detector::Anomaly(AnomalyDetector::new(anomaly.clone())))
+                }
+                Condition::Forecast(forecast) => {
+                    Some(Detector::Forecast(Forecaster::new(forecast.clone())))
+                }
+                Condition::Threshold(_) => None,
+            })
+            .collect();
         Alarm {
             config,
             samples: Vec::new(),
             previous_status: AlarmStatus::NoData,
+            incident: None,
+            evaluations: Vec::new(),
+            detectors,
         }
     }
 
@@ -67,6 +118,22 @@ impl Alarm {
     }
 
     pub fn check(&mut self, data: f64) -> Result<AlarmStatus, AlarmCheckError> {
+        self.check_at(data, &Local::now())
+    }
+
+    /// Checks a sample taken at `now`, e.g. a recorded one.
+    pub fn check_at(
+        &mut self,
+        data: f64,
+        now: &DateTime<Local>,
+    ) -> Result<AlarmStatus, AlarmCheckError> {
+        // Anomaly and forecast detectors learn from every sample, also while the alarm
+        // has no data yet.
+        let detected: Vec<Option<Evaluation>> = self
+            .detectors
+            .iter_mut()
+            .map(|detector| detector.as_mut().map(|d| d.observe(data, now)))
+            .collect();
         if self.samples.len() == self.config.sample_size {
             self.samples.remove(0);
         }
@@ -75,10 +142,17 @@ impl Alarm {
         if self.samples.len() < self.config.sample_size {
             return Ok(AlarmStatus::NoData);
         }
-        let mut res = true;
-        for cond in &self.config.conditions {
-            res = res & cond.check_condition(&self.samples);
-        }
+        self.evaluations = self
+            .config
+            .conditions
+            .iter()
+            .zip(detected)
+            .map(|(condition, detected)| match condition {
+                Condition::Threshold(threshold) => threshold.evaluate(&self.samples),
+                Condition::Anomaly(_) | Condition::Forecast(_) => detected.unwrap_or_default(),
+            })
+            .collect();
+        let res = self.evaluations.iter().all(|evaluation| evaluation.holds);
         Ok(match res {
             true => AlarmStatus::Alarm,
             false => AlarmStatus::Ok,
@@ -96,14 +170,36 @@ pub struct MetricConfiguration {
     pub dimension: Option<String>,
     pub alarms: Vec<AlarmConfiguration>,
     pub interval: u64,
+    #[serde(default)]
+    pub kind: MetricKind,
+    /// Shape of the samples of a synthetic metric.
+    pub synthetic: Option<SyntheticConfiguration>,
+}
+
+/// Where the samples of a metric come from.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum MetricKind {
+    /// Read from the system, the metric name selects what is read.
+    #[default]
+    #[serde(rename = "system")]
+    System,
+    /// Generated from `synthetic`, any name can be used, e.g. to test alarms and senders.
+    #[serde(rename = "synthetic")]
+    Synthetic,
 }
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct AlarmConfiguration {
     name: String,
     severity: AlarmSeverity,
-    conditions: Vec<ConditionConfiguration>,
+    conditions: Vec<Condition>,
     sample_size: usize,
+    #[serde(default)]
+    notify: Vec<String>,
+    template: Option<TemplateConfiguration>,
+    /// Steps sent while the alarm keeps firing.
+    #[serde(default)]
+    escalation: Vec<EscalationStep>,
 }
 
 impl AlarmConfiguration {
@@ -113,38 +209,68 @@ impl AlarmConfiguration {
     pub fn name(&self) -> String {
         self.name.clone()
     }
-    pub fn severity(&self) -> String {
-        self.severity.get_name()
+    pub fn severity(&self) -> AlarmSeverity {
+        self.severity
     }
-    pub fn conditions(&self) -> &Vec<ConditionConfiguration> {
+    pub fn conditions(&self) -> &Vec<Condition> {
         &self.conditions
     }
+    pub fn notify(&self) -> Vec<String> {
+        self.notify.clone()
+    }
+    pub fn template(&self) -> Option<&TemplateConfiguration> {
+        self.template.as_ref()
+    }
+    pub fn escalation(&self) -> &[EscalationStep] {
+        &self.escalation
+    }
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub enum AlarmStatus {
     Ok,
     Alarm,
     NoData,
 }
 
-#[derive(Deserialize, Debug, Clone)]
-pub enum AlarmSeverity {
-    #[serde(rename = "high")]
-    High,
-    #[serde(rename = "low")]
-    Low,
+/// A condition of an alarm, either a fixed threshold, an anomaly check or a forecast.
+#[derive(Debug, Clone)]
+pub enum Condition {
+    Anomaly(AnomalyConfiguration),
+    Forecast(ForecastConfiguration),
+    Threshold(ConditionConfiguration),
 }
 
-impl AlarmSeverity {
-    pub fn get_name(&self) -> String {
-        match self {
-            AlarmSeverity::High => String::from("high"),
-            AlarmSeverity::Low => String::from("low"),
-        }
+/// Picked by the `anomaly` or `predict` key rather than untagged, so an invalid condition
+/// reports its own error instead of matching no variant.
+impl<'de> serde::Deserialize<'de> for Condition {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let condition = if value.get("anomaly").is_some() {
+            serde_json::from_value(value).map(Condition::Anomaly)
+        } else if value.get("predict").is_some() {
+            serde_json::from_value(value).map(Condition::Forecast)
+        } else {
+            serde_json::from_value(value).map(Condition::Threshold)
+        };
+        condition.map_err(serde::de::Error::custom)
     }
 }
 
+/// Outcome of a condition on the latest samples.
+#[derive(Debug, Clone, Default)]
+pub struct Evaluation {
+    pub holds: bool,
+    /// Calculated value, the deviation in standard deviations for anomalies or the
+    /// seconds left until the predicted value for forecasts.
+    pub computed: f64,
+    pub threshold: f64,
+    pub comparison: String,
+    pub method: String,
+    /// Added to the notification description when the alarm fires.
+    pub description: Option<String>,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct ConditionConfiguration {
     comparison: Comparison,
@@ -166,8 +292,19 @@ impl ConditionConfiguration {
 
 impl ConditionConfiguration {
     pub fn check_condition(&self, data_set: &Vec<f64>) -> bool {
+        self.evaluate(data_set).holds
+    }
+
+    pub fn evaluate(&self, data_set: &Vec<f64>) -> Evaluation {
         let actual_data = self.method.calculate(data_set);
-        self.comparison.compare(actual_data, self.value)
+        Evaluation {
+            holds: self.comparison.compare(actual_data, self.value),
+            computed: actual_data,
+            threshold: self.value,
+            comparison: self.comparison.get_name(),
+            method: self.method.get_name(),
+            description: None,
+        }
     }
 }
 
@@ -188,7 +325,18 @@ pub enum Comparison {
 }
 
 impl Comparison {
-    fn compare(&self, actual: f64, expected: f64) -> bool {
+    pub fn get_name(&self) -> String {
+        String::from(match self {
+            Comparison::Greater => "g",
+            Comparison::GreaterAndEqual => "ge",
+            Comparison::Lesser => "l",
+            Comparison::LesserAndEqual => "le",
+            Comparison::Equal => "e",
+            Comparison::NotEqual => "n",
+        })
+    }
+
+    pub fn compare(&self, actual: f64, expected: f64) -> bool {
         match self {
             Comparison::Greater => actual > expected,
             Comparison::GreaterAndEqual => actual >= expected,
@@ -213,7 +361,16 @@ pub enum CalculationMethod {
 }
 
 impl CalculationMethod {
-    fn calculate(&self, data_set: &Vec<f64>) -> f64 {
+    pub fn get_name(&self) -> String {
+        String::from(match self {
+            CalculationMethod::Sum => "sum",
+            CalculationMethod::Avg => "avg",
+            CalculationMethod::Max => "max",
+            CalculationMethod::Min => "min",
+        })
+    }
+
+    pub fn calculate(&self, data_set: &Vec<f64>) -> f64 {
         match self {
             CalculationMethod::Sum => data_set.iter().sum::<f64>(),
             CalculationMethod::Max => data_set
@@ -0,0 +1,180 @@
+use crate::core::config::Evaluation;
+use serde_derive::Deserialize;
+use std::collections::VecDeque;
+
+/// Condition holding when the trend of the metric is projected to reach `predict`
+/// within `within` seconds, e.g. "disk reaches 100% within 24h".
+#[derive(Deserialize, Debug, Clone)]
+pub struct ForecastConfiguration {
+    /// Value the projected trend is checked against.
+    predict: f64,
+    /// Seconds, the condition holds when the value is projected to be reached sooner.
+    within: i64,
+    /// Seconds of samples the trend is fitted over.
+    #[serde(default = "default_history")]
+    history: i64,
+    /// Samples needed in the history before a trend is projected.
+    #[serde(default = "default_min_samples")]
+    min_samples: usize,
+}
+
+fn default_history() -> i64 {
+    6 * 60 * 60
+}
+
+fn default_min_samples() -> usize {
+    5
+}
+
+/// Samples of a forecast condition, fitted with a least squares line.
+#[derive(Debug, Clone)]
+pub struct Forecaster {
+    config: ForecastConfiguration,
+    samples: VecDeque<(i64, f64)>,
+}
+
+impl Forecaster {
+    pub fn new(config: ForecastConfiguration) -> Forecaster {
+        Forecaster {
+            config,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Records `value` and projects the seconds left until the trend reaches `predict`.
+    pub fn observe(&mut self, value: f64, timestamp: i64) -> Evaluation {
+        self.samples.push_back((timestamp, value));
+        while self.samples.len() > 1 && self.samples[0].0 < timestamp - self.config.history {
+            self.samples.pop_front();
+        }
+        let remaining = if self.samples.len() < self.config.min_samples {
+            None
+        } else {
+            self.remaining(timestamp)
+        };
+        let target = self.config.predict;
+        Evaluation {
+            holds: remaining.is_some_and(|seconds| seconds <= self.config.within as f64),
+            computed: remaining.unwrap_or(f64::INFINITY),
+            threshold: self.config.within as f64,
+            comparison: String::from("le"),
+            method: String::from("predict"),
+            description: remaining.map(|seconds| match seconds {
+                seconds if seconds > 0.0 => {
+                    format!("projected to reach {} in {}", target, duration(seconds))
+                }
+                _ => format!("trend reached {}", target),
+            }),
+        }
+    }
+
+    /// Seconds until the fitted line crosses `predict`, 0 when it already crossed it within
+    /// the history and None when it heads away from it.
+    fn remaining(&self, now: i64) -> Option<f64> {
+        let origin = self.samples[0].0;
+        let count = self.samples.len() as f64;
+        let mean_x = self
+            .samples
+            .iter()
+            .map(|(timestamp, _)| (timestamp - origin) as f64)
+            .sum::<f64>()
+            / count;
+        let mean_y = self.samples.iter().map(|(_, value)| value).sum::<f64>() / count;
+        let (covariance, variance) =
+            self.samples
+                .iter()
+                .fold((0.0, 0.0), |(covariance, variance), (timestamp, value)| {
+                    let dx = (timestamp - origin) as f64 - mean_x;
+                    (covariance + dx * (value - mean_y), variance + dx * dx)
+                });
+        if variance == 0.0 || covariance == 0.0 {
+            return None;
+        }
+        let slope = covariance / variance;
+        let fitted = mean_y + slope * ((now - origin) as f64 - mean_x);
+        let seconds = (self.config.predict - fitted) / slope;
+        if seconds >= 0.0 {
+            return Some(seconds);
+        }
+        // Behind us, either the line crossed `predict` since the start of the history or
+        // it was already past it and moves away.
+        let start = mean_y - slope * mean_x;
+        if (start - self.config.predict) * (fitted - self.config.predict) <= 0.0 {
+            Some(0.0)
+        } else {
+            None
+        }
+    }
+}
+
+/// Seconds as hours and minutes, e.g. "5h 12m".
+fn duration(seconds: f64) -> String {
+    let minutes = (seconds / 60.0).round() as i64;
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{}m", minutes),
+        (hours, minutes) => format!("{}h {}m", hours, minutes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forecaster(predict: f64, within: i64) -> Forecaster {
+        Forecaster::new(
+            serde_json::from_value(serde_json::json!({"predict": predict, "within": within}))
+                .unwrap(),
+        )
+    }
+
+    /// Observes a sample a minute apart for each value and returns the last evaluation.
+    fn observe(forecaster: &mut Forecaster, values: &[f64]) -> Evaluation {
+        let mut evaluation = Evaluation::default();
+        for (minute, value) in values.iter().enumerate() {
+            evaluation = forecaster.observe(*value, minute as i64 * 60);
+        }
+        evaluation
+    }
+
+    #[test]
+    fn needs_min_samples() {
+        let evaluation = observe(&mut forecaster(20.0, 3600), &[10.0, 11.0, 12.0, 13.0]);
+        assert!(!evaluation.holds);
+        assert_eq!(evaluation.computed, f64::INFINITY);
+    }
+
+    #[test]
+    fn projects_the_fitted_trend() {
+        // One per minute, 14 at the last sample leaves 6 minutes to 20.
+        let rising = [10.0, 11.0, 12.0, 13.0, 14.0];
+        let evaluation = observe(&mut forecaster(20.0, 600), &rising);
+        assert!(evaluation.holds);
+        assert!((evaluation.computed - 360.0).abs() < 1e-9);
+        assert_eq!(
+            evaluation.description.as_deref(),
+            Some("projected to reach 20 in 6m")
+        );
+        assert!(!observe(&mut forecaster(20.0, 300), &rising).holds);
+    }
+
+    #[test]
+    fn holds_once_the_trend_crossed() {
+        let evaluation = observe(
+            &mut forecaster(100.0, 3600),
+            &[96.0, 98.0, 100.0, 102.0, 104.0],
+        );
+        assert!(evaluation.holds);
+        assert_eq!(evaluation.computed, 0.0);
+        assert_eq!(evaluation.description.as_deref(), Some("trend reached 100"));
+    }
+
+    #[test]
+    fn does_not_hold_heading_away() {
+        let evaluation = observe(
+            &mut forecaster(100.0, 3600),
+            &[60.0, 58.0, 56.0, 54.0, 52.0],
+        );
+        assert!(!evaluation.holds);
+        assert_eq!(evaluation.description, None);
+    }
+}
@@ -1,18 +1,29 @@
 use crate::core::config::{Alarm, Metric, MetricConfiguration, SampleCollectError};
+use crate::system::{host, SystemInfo};
 use std::borrow::BorrowMut;
-use systemstat::{Duration, Platform, System};
+use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Debug)]
 pub struct CpuUsageMetric {
     interval: u64,
     alarms: Vec<Alarm>,
+    system: Arc<dyn SystemInfo>,
 }
 
 impl CpuUsageMetric {
     pub fn new(config: MetricConfiguration) -> CpuUsageMetric {
+        CpuUsageMetric::new_with_system(config, host())
+    }
+
+    pub fn new_with_system(
+        config: MetricConfiguration,
+        system: Arc<dyn SystemInfo>,
+    ) -> CpuUsageMetric {
         CpuUsageMetric {
             interval: config.interval,
             alarms: Alarm::from(config.alarms),
+            system,
         }
     }
 }
@@ -23,13 +34,8 @@ impl Metric for CpuUsageMetric {
     }
 
     fn poll_metric(&mut self) -> Result<f64, SampleCollectError> {
-        let sys = System::new();
-        match sys.cpu_load_aggregate() {
-            Ok(cpu) => {
-                std::thread::sleep(Duration::from_secs(1));
-                let load = cpu.done().unwrap();
-                Ok(load.user as f64)
-            }
+        match self.system.cpu_user(Duration::from_secs(1)) {
+            Ok(user) => Ok(user),
             Err(err) => Err(SampleCollectError::new(format!(
                 "Error while gathering info for Cpu: {}",
                 err
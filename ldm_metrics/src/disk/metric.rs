@@ -1,19 +1,29 @@
 use crate::core::config::{Alarm, Metric, MetricConfiguration, SampleCollectError};
+use crate::system::{host, SystemInfo};
 use std::borrow::BorrowMut;
-use systemstat::{Platform, System};
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub struct DiskUsageMetric {
     interval: u64,
     dimension: String,
     alarms: Vec<Alarm>,
+    system: Arc<dyn SystemInfo>,
 }
 
 impl DiskUsageMetric {
     pub fn new(config: MetricConfiguration) -> DiskUsageMetric {
+        DiskUsageMetric::new_with_system(config, host())
+    }
+
+    pub fn new_with_system(
+        config: MetricConfiguration,
+        system: Arc<dyn SystemInfo>,
+    ) -> DiskUsageMetric {
         DiskUsageMetric {
             interval: config.interval,
             alarms: Alarm::from(config.alarms),
+            system,
             dimension: config
                 .dimension
                 .expect("Dimension must be specified in disk metric"),
@@ -26,14 +36,17 @@ impl Metric for DiskUsageMetric {
         String::from(format!("disk::usage::{}", self.dimension))
     }
 
+    fn get_dimension(&self) -> Option<String> {
+        Some(self.dimension.clone())
+    }
+
     fn poll_metric(&mut self) -> Result<f64, SampleCollectError> {
-        let sys = System::new();
-        match sys.mounts() {
+        match self.system.mounts() {
             Ok(mounts) => {
                 for mount in mounts {
-                    if self.dimension.eq(mount.fs_mounted_on.as_str()) {
-                        let total = mount.total.as_u64() as f64;
-                        let avail = mount.avail.as_u64() as f64;
+                    if self.dimension.eq(mount.mounted_on.as_str()) {
+                        let total = mount.total as f64;
+                        let avail = mount.avail as f64;
                         let usage = 100.0 - 100.0 * avail / total;
                         return Ok(usage);
                     }
@@ -0,0 +1,247 @@
+use crate::collector::{incident_start, notify, CollectContext};
+use chrono::Utc;
+use ldm_commons::{AlarmSeverity, Notification};
+use serde_derive::Deserialize;
+use std::collections::HashMap;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+/// Checks escalation steps even while no sample arrives.
+const TICK: u64 = 5;
+
+/// Extra notification sent once an alarm has been firing for `after` seconds.
+#[derive(Deserialize, Debug, Clone)]
+pub struct EscalationStep {
+    pub after: i64,
+    /// Senders notified by this step.
+    pub notify: Vec<String>,
+    /// Overrides the severity of the alarm, e.g. to page with a higher priority.
+    pub severity: Option<AlarmSeverity>,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum EscalationCommands {
+    /// Alarm is firing, the first one starts its escalation.
+    Fire {
+        key: String,
+        notification: Notification,
+        steps: Vec<EscalationStep>,
+    },
+    Resolve {
+        key: String,
+    },
+    Stop,
+}
+
+#[derive(Debug)]
+struct Escalation {
+    /// Time the incident opened, so a restart does not restart the steps.
+    since: i64,
+    notification: Notification,
+    steps: Vec<EscalationStep>,
+    /// Number of steps already sent, also before a restart.
+    sent: usize,
+}
+
+/// Sends the escalation steps of firing alarms, paused while they are acknowledged.
+#[derive(Debug)]
+pub(crate) struct Escalator {
+    escalations: HashMap<String, Escalation>,
+    context: CollectContext,
+}
+
+impl Escalator {
+    pub(crate) fn new(context: CollectContext) -> Escalator {
+        Escalator {
+            escalations: HashMap::new(),
+            context,
+        }
+    }
+
+    pub fn start(&mut self, rx: Receiver<EscalationCommands>) {
+        loop {
+            match rx.recv_timeout(Duration::from_secs(TICK)) {
+                Ok(EscalationCommands::Stop) => {
+                    info!("Stopping Escalator");
+                    break;
+                }
+                Ok(command) => self.record(command),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(err) => {
+                    error!("{}", err);
+                    break;
+                }
+            }
+            self.escalate(Utc::now().timestamp());
+        }
+    }
+
+    pub(crate) fn record(&mut self, command: EscalationCommands) {
+        match command {
+            EscalationCommands::Fire {
+                key,
+                notification,
+                mut steps,
+            } => match self.escalations.get_mut(&key) {
+                Some(escalation) => escalation.notification = notification,
+                None => {
+                    steps.sort_by_key(|step| step.after);
+                    let since = notification
+                        .context
+                        .incident
+                        .as_deref()
+                        .and_then(incident_start)
+                        .unwrap_or(notification.context.timestamp);
+                    let sent = self
+                        .context
+                        .state
+                        .escalated(&key, notification.context.incident.as_deref());
+                    self.escalations.insert(
+                        key,
+                        Escalation {
+                            since,
+                            notification,
+                            steps,
+                            sent,
+                        },
+                    );
+                }
+            },
+            EscalationCommands::Resolve { key } => {
+                self.escalations.remove(&key);
+            }
+            EscalationCommands::Stop => {}
+        }
+    }
+
+    pub(crate) fn escalate(&mut self, now: i64) {
+        for (key, escalation) in self.escalations.iter_mut() {
+            if self
+                .context
+                .acknowledgements
+                .acknowledged(&escalation.notification)
+            {
+                continue;
+            }
+            while let Some(step) = escalation.steps.get(escalation.sent) {
+                if now - escalation.since < step.after {
+                    break;
+                }
+                escalation.sent += 1;
+                self.context.state.escalate(key, escalation.sent);
+                info!(
+                    "Escalating {} to {:?}",
+                    escalation.notification.alarm_name(),
+                    step.notify
+                );
+                notify(
+                    build_notification(&escalation.notification, step, now - escalation.since),
+                    &self.context,
+                );
+            }
+        }
+    }
+}
+
+fn build_notification(firing: &Notification, step: &EscalationStep, elapsed: i64) -> Notification {
+    let mut notification = firing.clone().routes(step.notify.clone());
+    if let Some(severity) = step.severity {
+        notification.severity = severity;
+    }
+    notification.description = format!(
+        "Escalated after {}m without resolving. {}",
+        elapsed / 60,
+        firing.description
+    );
+    notification
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::Alarm;
+    use ldm_commons::{AlarmContext, AlarmSenderCommands};
+    use std::sync::mpsc::channel;
+
+    const KEY: &str = "cpu::usage:High CPU";
+
+    fn fire(escalator: &mut Escalator, incident: &str, timestamp: i64) {
+        let notification = Notification::new(
+            String::from("High CPU"),
+            AlarmSeverity::High,
+            String::from("Alarm data set -> 0.9"),
+            String::from("cpu::usage"),
+            0.9,
+        )
+        .context(AlarmContext {
+            incident: Some(String::from(incident)),
+            timestamp,
+            ..AlarmContext::default()
+        });
+        escalator.record(EscalationCommands::Fire {
+            key: String::from(KEY),
+            notification,
+            steps: vec![EscalationStep {
+                after: 600,
+                notify: vec![String::from("oncall")],
+                severity: None,
+            }],
+        });
+    }
+
+    #[test]
+    fn steps_count_from_the_incident_start() {
+        let (tx, rx) = channel();
+        let mut escalator = Escalator::new(CollectContext::for_tests(tx));
+        // Restored after a restart, the incident opened before this sample.
+        fire(&mut escalator, "cpu::usage:High CPU@1000", 1500);
+        escalator.escalate(1599);
+        assert!(rx.try_recv().is_err());
+        escalator.escalate(1600);
+        match rx.try_recv() {
+            Ok(AlarmSenderCommands::Send(notification)) => {
+                assert_eq!(notification.routes, vec![String::from("oncall")]);
+                assert!(notification.description.starts_with("Escalated after 10m"));
+            }
+            other => panic!("expected an escalation, got {:?}", other),
+        }
+        escalator.escalate(1700);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn restored_incidents_do_not_resend_steps() {
+        let (tx, rx) = channel();
+        let context = CollectContext::for_tests(tx);
+        let mut alarm = Alarm::new(
+            serde_json::from_value(serde_json::json!({
+                "name": "High CPU",
+                "severity": "high",
+                "conditions": [],
+                "sample_size": 1,
+            }))
+            .unwrap(),
+        );
+        alarm.incident = Some(String::from("cpu::usage:High CPU@1000"));
+        context.state.record(KEY, &alarm);
+        let mut escalator = Escalator::new(context.clone());
+        fire(&mut escalator, "cpu::usage:High CPU@1000", 1000);
+        escalator.escalate(1600);
+        assert!(rx.try_recv().is_ok());
+
+        // A restart escalates again from the saved states.
+        let mut restarted = Escalator::new(context.clone());
+        fire(&mut restarted, "cpu::usage:High CPU@1000", 1700);
+        restarted.escalate(1700);
+        assert!(rx.try_recv().is_err());
+
+        // The steps of a new incident are sent again.
+        alarm.incident = Some(String::from("cpu::usage:High CPU@2000"));
+        context.state.record(KEY, &alarm);
+        let mut next = Escalator::new(context);
+        fire(&mut next, "cpu::usage:High CPU@2000", 2000);
+        next.escalate(2600);
+        assert!(rx.try_recv().is_ok());
+    }
+}
@@ -0,0 +1,164 @@
+use crate::matcher::{label, AlarmMatcher};
+use ldm_commons::Notification;
+use serde_derive::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// While an alarm matching `source` fires, alarms matching `target` are not sent. A source
+/// stops inhibiting once it resolves, has no data or its metric fails to poll.
+#[derive(Deserialize, Debug, Clone)]
+pub struct InhibitRuleConfiguration {
+    pub source: AlarmMatcher,
+    pub target: AlarmMatcher,
+    /// Labels both alarms must share, any of metric, alarm, severity, dimension or device.
+    #[serde(default)]
+    pub equal: Vec<String>,
+}
+
+impl InhibitRuleConfiguration {
+    fn inhibits(&self, source: &Notification, target: &Notification) -> bool {
+        self.source.matches(source)
+            && self.target.matches(target)
+            && self
+                .equal
+                .iter()
+                .all(|name| label(source, name) == label(target, name))
+    }
+}
+
+pub(crate) fn key(notification: &Notification) -> String {
+    alarm_key(
+        &notification.metric,
+        notification.context.dimension.as_deref(),
+        notification.alarm_name(),
+    )
+}
+
+/// Identifies an alarm of a metric, used to track which alarms are firing.
+pub fn alarm_key(metric: &str, dimension: Option<&str>, alarm: &str) -> String {
+    format!("{}|{}|{}", metric, dimension.unwrap_or(""), alarm)
+}
+
+/// Firing alarms and the inhibition rules applied to them, shared by all metrics.
+#[derive(Debug, Clone, Default)]
+pub struct Inhibitor {
+    rules: Arc<Vec<InhibitRuleConfiguration>>,
+    firing: Arc<RwLock<HashMap<String, Notification>>>,
+}
+
+impl Inhibitor {
+    pub fn new(rules: Vec<InhibitRuleConfiguration>) -> Inhibitor {
+        Inhibitor {
+            rules: Arc::new(rules),
+            firing: Arc::default(),
+        }
+    }
+
+    pub fn fire(&self, notification: &Notification) {
+        self.firing
+            .write()
+            .unwrap()
+            .insert(key(notification), notification.clone());
+    }
+
+    pub fn resolve(&self, key: &str) {
+        self.firing.write().unwrap().remove(key);
+    }
+
+    pub fn firing(&self) -> Vec<Notification> {
+        self.firing.read().unwrap().values().cloned().collect()
+    }
+
+    /// Name of a firing alarm that inhibits the notification.
+    pub fn inhibited_by(&self, notification: &Notification) -> Option<String> {
+        if self.rules.is_empty() {
+            return None;
+        }
+        let target = key(notification);
+        let firing = self.firing.read().unwrap();
+        firing
+            .iter()
+            .filter(|(source, _)| **source != target)
+            .find(|(_, source)| {
+                self.rules
+                    .iter()
+                    .any(|rule| rule.inhibits(source, notification))
+            })
+            .map(|(_, source)| String::from(source.alarm_name()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ldm_commons::{AlarmContext, AlarmSeverity};
+
+    fn notification(alarm: &str, severity: AlarmSeverity, dimension: &str) -> Notification {
+        Notification::new(
+            String::from(alarm),
+            severity,
+            String::new(),
+            format!("disk::usage::{}", dimension),
+            95.0,
+        )
+        .context(AlarmContext {
+            dimension: Some(String::from(dimension)),
+            ..AlarmContext::default()
+        })
+    }
+
+    fn inhibitor() -> Inhibitor {
+        Inhibitor::new(vec![serde_json::from_value(serde_json::json!({
+            "source": {"severity": "high"},
+            "target": {"severity": "low"},
+            "equal": ["dimension"],
+        }))
+        .unwrap()])
+    }
+
+    #[test]
+    fn firing_source_inhibits_targets_sharing_the_labels() {
+        let inhibitor = inhibitor();
+        let source = notification("Disk is full", AlarmSeverity::High, "/");
+        inhibitor.fire(&source);
+        assert_eq!(
+            inhibitor
+                .inhibited_by(&notification("Disk is filling", AlarmSeverity::Low, "/"))
+                .as_deref(),
+            Some("Disk is full")
+        );
+        // Another dimension, or a target the rule does not select, is sent.
+        assert!(inhibitor
+            .inhibited_by(&notification(
+                "Disk is filling",
+                AlarmSeverity::Low,
+                "/backup"
+            ))
+            .is_none());
+        assert!(inhibitor
+            .inhibited_by(&notification("Disk is broken", AlarmSeverity::High, "/"))
+            .is_none());
+    }
+
+    #[test]
+    fn resolved_source_stops_inhibiting() {
+        let inhibitor = inhibitor();
+        let source = notification("Disk is full", AlarmSeverity::High, "/");
+        let target = notification("Disk is filling", AlarmSeverity::Low, "/");
+        inhibitor.fire(&source);
+        inhibitor.resolve(&key(&source));
+        assert!(inhibitor.inhibited_by(&target).is_none());
+    }
+
+    #[test]
+    fn an_alarm_does_not_inhibit_itself() {
+        let inhibitor = Inhibitor::new(vec![serde_json::from_value(serde_json::json!({
+            "source": {},
+            "target": {},
+        }))
+        .unwrap()]);
+        let alarm = notification("Disk is full", AlarmSeverity::High, "/");
+        inhibitor.fire(&alarm);
+        assert!(inhibitor.inhibited_by(&alarm).is_none());
+    }
+}
@@ -1,13 +1,22 @@
+pub mod ack;
 pub mod collector;
 pub mod core;
 pub mod cpu;
 pub mod disk;
 pub mod errors;
+pub mod escalation;
+pub mod inhibit;
+pub mod matcher;
 pub mod mem;
 pub mod network;
+pub mod rules;
+pub mod replay;
+pub mod silence;
+pub mod state;
+pub mod synthetic;
+pub mod system;
 pub mod temp;
 
-#[cfg_attr(test, feature(proc_macro_hygiene))]
 #[macro_use]
 extern crate log;
 
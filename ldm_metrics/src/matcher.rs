@@ -0,0 +1,144 @@
+use ldm_commons::{AlarmSeverity, Notification};
+use serde_derive::{Deserialize, Serialize};
+
+/// Selects alarms by their labels. Empty fields match everything and `*` matches
+/// any text, e.g. `metric = "network::*"`.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+pub struct AlarmMatcher {
+    /// Series name, e.g. `disk::usage::/backup` for a metric with a dimension.
+    pub metric: Option<String>,
+    pub alarm: Option<String>,
+    /// Exact severity, a misspelled one fails to load instead of never matching.
+    pub severity: Option<AlarmSeverity>,
+    pub dimension: Option<String>,
+}
+
+impl AlarmMatcher {
+    pub fn matches(&self, notification: &Notification) -> bool {
+        matcher(&self.metric, Some(&notification.metric))
+            && matcher(&self.alarm, Some(notification.alarm_name()))
+            && self
+                .severity
+                .is_none_or(|severity| severity == notification.severity)
+            && matcher(&self.dimension, notification.context.dimension.as_deref())
+    }
+}
+
+/// Value of a label used to compare two alarms, one of metric, alarm, severity,
+/// dimension or device.
+pub fn label<'a>(notification: &'a Notification, name: &str) -> Option<&'a str> {
+    match name {
+        "metric" => Some(&notification.metric),
+        "alarm" => Some(notification.alarm_name()),
+        "severity" => Some(notification.severity.as_str()),
+        "dimension" => notification.context.dimension.as_deref(),
+        "device" => Some(&notification.context.device.name),
+        _ => None,
+    }
+}
+
+fn matcher(pattern: &Option<String>, value: Option<&str>) -> bool {
+    match (pattern, value) {
+        (None, _) => true,
+        (Some(pattern), Some(value)) => glob(pattern, value),
+        (Some(_), None) => false,
+    }
+}
+
+pub fn glob(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    if !value.starts_with(first) {
+        return false;
+    }
+    let mut rest = &value[first.len()..];
+    let mut parts: Vec<&str> = parts.collect();
+    let last = match parts.pop() {
+        Some(last) => last,
+        None => return rest.is_empty(),
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ldm_commons::{AlarmContext, AlarmSeverity, Device};
+
+    fn notification() -> Notification {
+        Notification::new(
+            String::from("Disk is full"),
+            AlarmSeverity::High,
+            String::from("Alarm data set -> 95"),
+            String::from("disk::usage::/backup"),
+            95.0,
+        )
+        .context(AlarmContext {
+            dimension: Some(String::from("/backup")),
+            device: Device {
+                name: String::from("pi"),
+                ip: String::from("10.0.0.2"),
+            },
+            ..AlarmContext::default()
+        })
+    }
+
+    #[test]
+    fn glob_matches_any_text_for_stars() {
+        assert!(glob("disk::usage::/", "disk::usage::/"));
+        assert!(!glob("disk::usage::/", "disk::usage::/backup"));
+        assert!(glob("disk::*", "disk::usage::/backup"));
+        assert!(glob("*backup", "disk::usage::/backup"));
+        assert!(glob("disk::*::/b*p", "disk::usage::/backup"));
+        assert!(glob("*", ""));
+        assert!(!glob("network::*", "disk::usage::/"));
+        // The ends may not overlap, "a*a" needs two characters.
+        assert!(!glob("a*a", "a"));
+    }
+
+    #[test]
+    fn every_set_field_has_to_match() {
+        let notification = notification();
+        assert!(AlarmMatcher::default().matches(&notification));
+        let matcher = AlarmMatcher {
+            metric: Some(String::from("disk::usage::*")),
+            severity: Some(AlarmSeverity::High),
+            dimension: Some(String::from("/backup")),
+            ..AlarmMatcher::default()
+        };
+        assert!(matcher.matches(&notification));
+        let other_alarm = AlarmMatcher {
+            alarm: Some(String::from("High CPU*")),
+            ..matcher
+        };
+        assert!(!other_alarm.matches(&notification));
+    }
+
+    #[test]
+    fn misspelled_severities_are_rejected() {
+        let matcher: AlarmMatcher =
+            serde_json::from_value(serde_json::json!({"severity": "critical"})).unwrap();
+        assert_eq!(matcher.severity, Some(AlarmSeverity::Critical));
+        assert!(
+            serde_json::from_value::<AlarmMatcher>(serde_json::json!({"severity": "hihg"}))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn labels_of_a_notification() {
+        let notification = notification();
+        assert_eq!(label(&notification, "metric"), Some("disk::usage::/backup"));
+        assert_eq!(label(&notification, "alarm"), Some("Disk is full"));
+        assert_eq!(label(&notification, "severity"), Some("high"));
+        assert_eq!(label(&notification, "dimension"), Some("/backup"));
+        assert_eq!(label(&notification, "device"), Some("pi"));
+        assert_eq!(label(&notification, "colour"), None);
+    }
+}
@@ -1,18 +1,28 @@
 use crate::core::config::{Alarm, Metric, MetricConfiguration, SampleCollectError};
+use crate::system::{host, SystemInfo};
 use std::borrow::BorrowMut;
-use systemstat::{Platform, System};
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub struct MemoryUsageMetric {
     interval: u64,
     alarms: Vec<Alarm>,
+    system: Arc<dyn SystemInfo>,
 }
 
 impl MemoryUsageMetric {
     pub fn new(config: MetricConfiguration) -> MemoryUsageMetric {
+        MemoryUsageMetric::new_with_system(config, host())
+    }
+
+    pub fn new_with_system(
+        config: MetricConfiguration,
+        system: Arc<dyn SystemInfo>,
+    ) -> MemoryUsageMetric {
         MemoryUsageMetric {
             interval: config.interval,
             alarms: Alarm::from(config.alarms),
+            system,
         }
     }
 }
@@ -23,10 +33,10 @@ impl Metric for MemoryUsageMetric {
     }
 
     fn poll_metric(&mut self) -> Result<f64, SampleCollectError> {
-        return match System::new().memory() {
+        return match self.system.memory() {
             Ok(memory) => {
-                let total = memory.total.0;
-                let free = memory.free.0;
+                let total = memory.total;
+                let free = memory.free;
                 let used = total - free;
                 Ok(100.0 * used as f64 / total as f64)
             }
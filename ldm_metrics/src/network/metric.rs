@@ -1,10 +1,11 @@
 use crate::core::config::{Alarm, Metric, MetricConfiguration, SampleCollectError};
+use crate::system::{host, NetworkInfo, SystemInfo};
 use std::borrow::BorrowMut;
-use systemstat::{Duration, NetworkStats, Platform, System};
+use std::sync::Arc;
+use std::time::Duration;
 
-fn get_stat(name: String) -> Result<NetworkStats, SampleCollectError> {
-    let sys = System::new();
-    match sys.network_stats(name.as_str()) {
+fn get_stat(system: &dyn SystemInfo, name: &str) -> Result<NetworkInfo, SampleCollectError> {
+    match system.network(name) {
         Ok(stat) => Ok(stat),
         Err(err) => {
             return Err(SampleCollectError::new(format!(
@@ -20,16 +21,25 @@ pub struct NetworkRxUsageMetric {
     interval: u64,
     alarms: Vec<Alarm>,
     dimension: String,
+    system: Arc<dyn SystemInfo>,
 }
 
 impl NetworkRxUsageMetric {
     pub fn new(config: MetricConfiguration) -> NetworkRxUsageMetric {
+        NetworkRxUsageMetric::new_with_system(config, host())
+    }
+
+    pub fn new_with_system(
+        config: MetricConfiguration,
+        system: Arc<dyn SystemInfo>,
+    ) -> NetworkRxUsageMetric {
         NetworkRxUsageMetric {
             interval: config.interval,
             alarms: Alarm::from(config.alarms),
             dimension: config
                 .dimension
                 .expect("Dimension must be specified in disk metric"),
+            system,
         }
     }
 }
@@ -39,11 +49,15 @@ impl Metric for NetworkRxUsageMetric {
         String::from(format!("network::rx::usage::{}", self.dimension))
     }
 
+    fn get_dimension(&self) -> Option<String> {
+        Some(self.dimension.clone())
+    }
+
     fn poll_metric(&mut self) -> Result<f64, SampleCollectError> {
-        let first = get_stat(self.dimension.clone())?;
-        std::thread::sleep(Duration::from_secs(1));
-        let second = get_stat(self.dimension.clone())?;
-        Ok((second.rx_bytes.0 - first.rx_bytes.0) as f64 / 1000.0)
+        let first = get_stat(self.system.as_ref(), &self.dimension)?;
+        self.system.wait(Duration::from_secs(1));
+        let second = get_stat(self.system.as_ref(), &self.dimension)?;
+        Ok(second.rx_bytes.saturating_sub(first.rx_bytes) as f64 / 1000.0)
     }
 
     fn get_alarms(&mut self) -> &mut [Alarm] {
@@ -60,16 +74,25 @@ pub struct NetworkTxUsageMetric {
     interval: u64,
     alarms: Vec<Alarm>,
     dimension: String,
+    system: Arc<dyn SystemInfo>,
 }
 
 impl NetworkTxUsageMetric {
     pub fn new(config: MetricConfiguration) -> NetworkTxUsageMetric {
+        NetworkTxUsageMetric::new_with_system(config, host())
+    }
+
+    pub fn new_with_system(
+        config: MetricConfiguration,
+        system: Arc<dyn SystemInfo>,
+    ) -> NetworkTxUsageMetric {
         NetworkTxUsageMetric {
             interval: config.interval,
             alarms: Alarm::from(config.alarms),
             dimension: config
                 .dimension
                 .expect("Dimension must be specified in disk metric"),
+            system,
         }
     }
 }
@@ -79,11 +102,15 @@ impl Metric for NetworkTxUsageMetric {
         String::from(format!("network::tx::usage::{}", self.dimension))
     }
 
+    fn get_dimension(&self) -> Option<String> {
+        Some(self.dimension.clone())
+    }
+
     fn poll_metric(&mut self) -> Result<f64, SampleCollectError> {
-        let first = get_stat(self.dimension.clone())?;
-        std::thread::sleep(Duration::from_secs(1));
-        let second = get_stat(self.dimension.clone())?;
-        Ok((second.tx_bytes.0 - first.tx_bytes.0) as f64 / 1000.0)
+        let first = get_stat(self.system.as_ref(), &self.dimension)?;
+        self.system.wait(Duration::from_secs(1));
+        let second = get_stat(self.system.as_ref(), &self.dimension)?;
+        Ok(second.tx_bytes.saturating_sub(first.tx_bytes) as f64 / 1000.0)
     }
 
     fn get_alarms(&mut self) -> &mut [Alarm] {
@@ -100,16 +127,25 @@ pub struct NetworkRxTotalMetric {
     interval: u64,
     alarms: Vec<Alarm>,
     dimension: String,
+    system: Arc<dyn SystemInfo>,
 }
 
 impl NetworkRxTotalMetric {
     pub fn new(config: MetricConfiguration) -> NetworkRxTotalMetric {
+        NetworkRxTotalMetric::new_with_system(config, host())
+    }
+
+    pub fn new_with_system(
+        config: MetricConfiguration,
+        system: Arc<dyn SystemInfo>,
+    ) -> NetworkRxTotalMetric {
         NetworkRxTotalMetric {
             interval: config.interval,
             alarms: Alarm::from(config.alarms),
             dimension: config
                 .dimension
                 .expect("Dimension must be specified in disk metric"),
+            system,
         }
     }
 }
@@ -119,9 +155,13 @@ impl Metric for NetworkRxTotalMetric {
         String::from(format!("network::rx::total::{}", self.dimension))
     }
 
+    fn get_dimension(&self) -> Option<String> {
+        Some(self.dimension.clone())
+    }
+
     fn poll_metric(&mut self) -> Result<f64, SampleCollectError> {
-        let stat = get_stat(self.dimension.clone())?;
-        Ok((stat.rx_bytes.0) as f64 / 1000.0)
+        let stat = get_stat(self.system.as_ref(), &self.dimension)?;
+        Ok(stat.rx_bytes as f64 / 1000.0)
     }
 
     fn get_alarms(&mut self) -> &mut [Alarm] {
@@ -138,16 +178,25 @@ pub struct NetworkTxTotalMetric {
     interval: u64,
     alarms: Vec<Alarm>,
     dimension: String,
+    system: Arc<dyn SystemInfo>,
 }
 
 impl NetworkTxTotalMetric {
     pub fn new(config: MetricConfiguration) -> NetworkTxTotalMetric {
+        NetworkTxTotalMetric::new_with_system(config, host())
+    }
+
+    pub fn new_with_system(
+        config: MetricConfiguration,
+        system: Arc<dyn SystemInfo>,
+    ) -> NetworkTxTotalMetric {
         NetworkTxTotalMetric {
             interval: config.interval,
             alarms: Alarm::from(config.alarms),
             dimension: config
                 .dimension
                 .expect("Dimension must be specified in disk metric"),
+            system,
         }
     }
 }
@@ -157,9 +206,13 @@ impl Metric for NetworkTxTotalMetric {
         String::from(format!("network::tx::total::{}", self.dimension))
     }
 
+    fn get_dimension(&self) -> Option<String> {
+        Some(self.dimension.clone())
+    }
+
     fn poll_metric(&mut self) -> Result<f64, SampleCollectError> {
-        let stat = get_stat(self.dimension.clone())?;
-        Ok((stat.tx_bytes.0) as f64 / 1000.0)
+        let stat = get_stat(self.system.as_ref(), &self.dimension)?;
+        Ok(stat.tx_bytes as f64 / 1000.0)
     }
 
     fn get_alarms(&mut self) -> &mut [Alarm] {
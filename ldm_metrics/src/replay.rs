@@ -0,0 +1,126 @@
+use crate::errors::Error;
+use chrono::DateTime;
+use ldm_commons::MetricData;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// Reads recorded samples, either json lines as written by the file consumer or csv lines
+/// of `timestamp,metric,value`. Timestamps are unix seconds or RFC3339, a csv header is skipped.
+pub fn read_samples(path: &Path) -> Result<Vec<MetricData>, Error> {
+    let file = File::open(path)
+        .map_err(|err| Error::Generic(format!("Error while opening {:?}: {}", path, err)))?;
+    let mut samples = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line =
+            line.map_err(|err| Error::Generic(format!("Error while reading {:?}: {}", path, err)))?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let sample = if line.starts_with('{') {
+            serde_json::from_str(line).map_err(|err| err.to_string())
+        } else {
+            parse_csv(line)
+        };
+        match sample {
+            Ok(sample) => samples.push(sample),
+            Err(_) if index == 0 && !line.starts_with('{') => {}
+            Err(err) => {
+                return Err(Error::Generic(format!(
+                    "Invalid sample on line {} of {:?}: {}",
+                    index + 1,
+                    path,
+                    err
+                )))
+            }
+        }
+    }
+    Ok(samples)
+}
+
+fn parse_csv(line: &str) -> Result<MetricData, String> {
+    let (timestamp, rest) = line
+        .split_once(',')
+        .ok_or_else(|| String::from("expected timestamp,metric,value"))?;
+    let (metric, value) = rest
+        .rsplit_once(',')
+        .ok_or_else(|| String::from("expected timestamp,metric,value"))?;
+    let timestamp = timestamp.trim();
+    let timestamp = timestamp
+        .parse::<i64>()
+        .or_else(|_| DateTime::parse_from_rfc3339(timestamp).map(|time| time.timestamp()))
+        .map_err(|_| format!("invalid time {}", timestamp))?;
+    let value = value
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| format!("invalid value {}", value.trim()))?;
+    Ok(MetricData::new(
+        String::from(metric.trim()),
+        value,
+        timestamp,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// Writes `lines` to a temporary file, removed by the caller.
+    fn recording(name: &str, lines: &[&str]) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("ldm_replay_{}_{}.csv", name, std::process::id()));
+        fs::write(&path, lines.join("\n")).unwrap();
+        path
+    }
+
+    #[test]
+    fn csv_lines_are_parsed() {
+        let sample = parse_csv("1717408800, disk::usage::/backup , 95.5").unwrap();
+        assert_eq!(sample.name(), "disk::usage::/backup");
+        assert_eq!(sample.value(), 95.5);
+        assert_eq!(sample.timestamp(), 1_717_408_800);
+        let sample = parse_csv("2024-06-03T12:00:00+02:00,cpu::usage,0.9").unwrap();
+        assert_eq!(sample.timestamp(), 1_717_408_800);
+        assert_eq!(
+            parse_csv("yesterday,cpu::usage,0.9").unwrap_err(),
+            "invalid time yesterday"
+        );
+        assert_eq!(
+            parse_csv("1717408800,cpu::usage,high").unwrap_err(),
+            "invalid value high"
+        );
+        assert!(parse_csv("1717408800").is_err());
+    }
+
+    #[test]
+    fn header_and_blank_lines_are_skipped() {
+        let path = recording(
+            "header",
+            &[
+                "timestamp,metric,value",
+                "1717408800,cpu::usage,0.5",
+                "",
+                r#"{"metric": "cpu::usage", "value": 0.9, "timestamp": 1717408860}"#,
+            ],
+        );
+        let samples = read_samples(&path).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[1].value(), 0.9);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn bad_lines_fail_with_their_number() {
+        let path = recording(
+            "bad",
+            &["1717408800,cpu::usage,0.5", "1717408860,cpu::usage,high"],
+        );
+        let err = read_samples(&path).unwrap_err().to_string();
+        assert!(err.starts_with("Invalid sample on line 2"), "{}", err);
+        let _ = fs::remove_file(&path);
+        assert!(read_samples(&path).is_err());
+    }
+}
@@ -0,0 +1,478 @@
+use crate::collector::{notify, CollectContext};
+use crate::core::config::{AlarmSeverity, CalculationMethod, Comparison};
+use crate::matcher::glob;
+use chrono::Utc;
+use ldm_commons::history::{EventKind, HistoryEvent};
+use ldm_commons::template::TemplateConfiguration;
+use ldm_commons::{AlarmContext, Notification};
+use serde_derive::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+/// Checks `for` durations even while no sample arrives.
+const TICK: u64 = 5;
+
+/// Alarm evaluated over several metrics and alarm states, e.g.
+/// "cpu::usage avg > 0.8 AND temperature > 70 for 5m".
+#[derive(Deserialize, Debug, Clone)]
+pub struct CompositeAlarmConfiguration {
+    pub name: String,
+    pub severity: AlarmSeverity,
+    #[serde(default)]
+    pub operator: Operator,
+    /// Seconds the conditions must hold before the alarm fires.
+    #[serde(default, rename = "for")]
+    pub duration: i64,
+    pub conditions: Vec<CompositeCondition>,
+    #[serde(default)]
+    pub notify: Vec<String>,
+    pub template: Option<TemplateConfiguration>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum Operator {
+    #[default]
+    #[serde(rename = "all")]
+    All,
+    #[serde(rename = "any")]
+    Any,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum CompositeCondition {
+    /// Holds when any series matching `metric` satisfies the comparison. Series are
+    /// named like the metric, which ends with the dimension when it has one
+    /// (e.g. `disk::usage::/`), and `*` matches any text.
+    Metric {
+        metric: String,
+        #[serde(default = "default_method")]
+        method: CalculationMethod,
+        comparison: Comparison,
+        value: f64,
+        /// Seconds of samples the method is applied to, 0 uses the latest sample.
+        #[serde(default)]
+        window: i64,
+    },
+    /// Holds when an alarm matching `alarm` is (or with `firing = false` is not) firing.
+    Alarm {
+        alarm: String,
+        #[serde(default = "default_firing")]
+        firing: bool,
+    },
+}
+
+fn default_method() -> CalculationMethod {
+    CalculationMethod::Avg
+}
+
+fn default_firing() -> bool {
+    true
+}
+
+#[derive(Debug)]
+pub enum RuleEngineCommands {
+    Sample {
+        metric: String,
+        value: f64,
+        timestamp: i64,
+    },
+    AlarmState {
+        alarm: String,
+        firing: bool,
+    },
+    Stop,
+}
+
+#[derive(Debug)]
+struct Composite {
+    config: CompositeAlarmConfiguration,
+    /// Since when the conditions hold.
+    since: Option<i64>,
+    firing: bool,
+}
+
+/// Evaluates composite alarms from the samples and alarm states the collector feeds it.
+#[derive(Debug)]
+pub(crate) struct RuleEngine {
+    composites: Vec<Composite>,
+    series: HashMap<String, VecDeque<(i64, f64)>>,
+    alarms: HashMap<String, bool>,
+    retention: i64,
+    context: CollectContext,
+}
+
+impl RuleEngine {
+    pub(crate) fn new(
+        configurations: Vec<CompositeAlarmConfiguration>,
+        context: CollectContext,
+    ) -> RuleEngine {
+        let retention = configurations
+            .iter()
+            .flat_map(|config| config.conditions.iter())
+            .map(|condition| match condition {
+                CompositeCondition::Metric { window, .. } => *window,
+                CompositeCondition::Alarm { .. } => 0,
+            })
+            .max()
+            .unwrap_or(0);
+        RuleEngine {
+            composites: configurations
+                .into_iter()
+                .map(|config| Composite {
+                    config,
+                    since: None,
+                    firing: false,
+                })
+                .collect(),
+            series: HashMap::new(),
+            alarms: HashMap::new(),
+            retention,
+            context,
+        }
+    }
+
+    pub fn start(&mut self, rx: Receiver<RuleEngineCommands>) {
+        loop {
+            match rx.recv_timeout(Duration::from_secs(TICK)) {
+                Ok(RuleEngineCommands::Stop) => {
+                    info!("Stopping Rule Engine");
+                    break;
+                }
+                Ok(command) => self.record(command),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(err) => {
+                    error!("{}", err);
+                    break;
+                }
+            }
+            self.evaluate(Utc::now().timestamp());
+        }
+    }
+
+    pub(crate) fn record(&mut self, command: RuleEngineCommands) {
+        match command {
+            RuleEngineCommands::Sample {
+                metric,
+                value,
+                timestamp,
+            } => {
+                let samples = self.series.entry(metric).or_default();
+                samples.push_back((timestamp, value));
+                while samples.len() > 1 && samples[0].0 < timestamp - self.retention {
+                    samples.pop_front();
+                }
+            }
+            RuleEngineCommands::AlarmState { alarm, firing } => {
+                self.alarms.insert(alarm, firing);
+            }
+            RuleEngineCommands::Stop => {}
+        }
+    }
+
+    pub(crate) fn evaluate(&mut self, now: i64) {
+        for index in 0..self.composites.len() {
+            let results: Vec<(bool, String)> = self.composites[index]
+                .config
+                .conditions
+                .iter()
+                .map(|condition| self.check(condition, now))
+                .collect();
+            let composite = &mut self.composites[index];
+            let holds = match composite.config.operator {
+                Operator::All => results.iter().all(|(holds, _)| *holds),
+                Operator::Any => results.iter().any(|(holds, _)| *holds),
+            };
+            if !holds {
+                if composite.firing {
+                    info!("Composite alarm {} resolved", composite.config.name);
+                    let key = composite_key(&composite.config);
+                    self.context.inhibitor.resolve(&key);
+                    self.context.acknowledgements.resolve(&key);
+                    self.context
+                        .record(transition(&composite.config, "Alarm", "Ok", 0.0, now));
+                }
+                composite.since = None;
+                composite.firing = false;
+                continue;
+            }
+            let since = *composite.since.get_or_insert(now);
+            if composite.firing || now - since < composite.config.duration {
+                continue;
+            }
+            composite.firing = true;
+            let held: Vec<String> = results
+                .into_iter()
+                .filter(|(holds, _)| *holds)
+                .map(|(_, description)| description)
+                .collect();
+            self.context.record(transition(
+                &composite.config,
+                "Ok",
+                "Alarm",
+                held.len() as f64,
+                now,
+            ));
+            let notification = build_notification(&composite.config, held, &self.context, now);
+            self.context.inhibitor.fire(&notification);
+            notify(notification, &self.context);
+        }
+    }
+
+    /// Whether the condition holds, with a description of the values it saw.
+    fn check(&self, condition: &CompositeCondition, now: i64) -> (bool, String) {
+        match condition {
+            CompositeCondition::Metric {
+                metric,
+                method,
+                comparison,
+                value,
+                window,
+            } => {
+                for (name, samples) in &self.series {
+                    if !glob(metric, name) {
+                        continue;
+                    }
+                    let values: Vec<f64> = match samples.back() {
+                        Some((_, last)) if *window == 0 => vec![*last],
+                        _ => samples
+                            .iter()
+                            .filter(|(timestamp, _)| *timestamp >= now - window)
+                            .map(|(_, value)| *value)
+                            .collect(),
+                    };
+                    if values.is_empty() {
+                        continue;
+                    }
+                    let computed = method.calculate(&values);
+                    if comparison.compare(computed, *value) {
+                        return (
+                            true,
+                            format!(
+                                "{} {} {} {} {}",
+                                name,
+                                method.get_name(),
+                                computed,
+                                comparison.get_name(),
+                                value
+                            ),
+                        );
+                    }
+                }
+                (false, String::new())
+            }
+            CompositeCondition::Alarm { alarm, firing } => {
+                let found = self
+                    .alarms
+                    .iter()
+                    .filter(|(name, _)| glob(alarm, name))
+                    .find(|(_, state)| *state == firing);
+                match found {
+                    Some((name, _)) if *firing => (true, format!("{} is firing", name)),
+                    Some((name, _)) => (true, format!("{} is not firing", name)),
+                    None => (false, String::new()),
+                }
+            }
+        }
+    }
+}
+
+fn composite_key(config: &CompositeAlarmConfiguration) -> String {
+    crate::inhibit::alarm_key("composite", None, &config.name)
+}
+
+fn transition(
+    config: &CompositeAlarmConfiguration,
+    from: &str,
+    to: &str,
+    value: f64,
+    now: i64,
+) -> HistoryEvent {
+    HistoryEvent {
+        timestamp: now,
+        alarm: config.name.clone(),
+        metric: String::from("composite"),
+        dimension: None,
+        value,
+        kind: EventKind::Transition {
+            from: String::from(from),
+            to: String::from(to),
+            incident: None,
+        },
+    }
+}
+
+fn build_notification(
+    config: &CompositeAlarmConfiguration,
+    held: Vec<String>,
+    context: &CollectContext,
+    now: i64,
+) -> Notification {
+    let notification = Notification::new(
+        config.name.clone(),
+        config.severity,
+        format!("Conditions held -> {}", held.join(", ")),
+        String::from("composite"),
+        held.len() as f64,
+    )
+    .routes(config.notify.clone())
+    .context(AlarmContext {
+        alarm: config.name.clone(),
+        device: context.device.clone(),
+        timestamp: now,
+        ..AlarmContext::default()
+    });
+    match &config.template {
+        Some(template) => template.apply(notification),
+        None => notification,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ldm_commons::AlarmSenderCommands;
+    use std::sync::mpsc::channel;
+
+    fn composite(
+        operator: &str,
+        duration: i64,
+        conditions: serde_json::Value,
+    ) -> (RuleEngine, Receiver<AlarmSenderCommands>) {
+        let config = serde_json::from_value(serde_json::json!({
+            "name": "Composite",
+            "severity": "high",
+            "operator": operator,
+            "for": duration,
+            "conditions": conditions,
+        }))
+        .unwrap();
+        let (tx, rx) = channel();
+        let context = CollectContext::for_tests(tx);
+        (RuleEngine::new(vec![config], context), rx)
+    }
+
+    fn sample(engine: &mut RuleEngine, metric: &str, value: f64, timestamp: i64) {
+        engine.record(RuleEngineCommands::Sample {
+            metric: String::from(metric),
+            value,
+            timestamp,
+        });
+    }
+
+    fn alarm(engine: &mut RuleEngine, alarm: &str, firing: bool) {
+        engine.record(RuleEngineCommands::AlarmState {
+            alarm: String::from(alarm),
+            firing,
+        });
+    }
+
+    fn firing(engine: &RuleEngine) -> bool {
+        engine.composites[0].firing
+    }
+
+    #[test]
+    fn series_are_keyed_by_metric_name() {
+        let (mut engine, _notifications) = composite(
+            "all",
+            0,
+            serde_json::json!([{"metric": "disk::usage::/", "comparison": "g", "value": 90.0}]),
+        );
+        sample(&mut engine, "disk::usage::/", 95.0, 100);
+        assert!(engine.series.contains_key("disk::usage::/"));
+        engine.evaluate(100);
+        assert!(firing(&engine));
+    }
+
+    #[test]
+    fn all_needs_every_condition() {
+        let (mut engine, notifications) = composite(
+            "all",
+            0,
+            serde_json::json!([
+                {"metric": "cpu::usage", "comparison": "g", "value": 0.8},
+                {"alarm": "Temperature*"},
+            ]),
+        );
+        sample(&mut engine, "cpu::usage", 0.9, 100);
+        engine.evaluate(100);
+        assert!(!firing(&engine));
+        alarm(&mut engine, "Temperature is too high", true);
+        engine.evaluate(101);
+        assert!(firing(&engine));
+        match notifications.try_recv() {
+            Ok(AlarmSenderCommands::Send(notification)) => {
+                assert_eq!(notification.alarm_name(), "Composite")
+            }
+            other => panic!("expected a notification, got {:?}", other),
+        }
+        alarm(&mut engine, "Temperature is too high", false);
+        engine.evaluate(102);
+        assert!(!firing(&engine));
+    }
+
+    #[test]
+    fn any_needs_one_condition() {
+        let (mut engine, _notifications) = composite(
+            "any",
+            0,
+            serde_json::json!([
+                {"metric": "disk::usage::*", "comparison": "g", "value": 90.0},
+                {"alarm": "Memory*"},
+            ]),
+        );
+        sample(&mut engine, "disk::usage::/", 50.0, 100);
+        engine.evaluate(100);
+        assert!(!firing(&engine));
+        sample(&mut engine, "disk::usage::/backup", 95.0, 101);
+        engine.evaluate(101);
+        assert!(firing(&engine));
+    }
+
+    #[test]
+    fn fires_once_conditions_held_for_the_duration() {
+        let (mut engine, _notifications) = composite(
+            "all",
+            60,
+            serde_json::json!([{"metric": "temperature", "comparison": "g", "value": 70.0}]),
+        );
+        sample(&mut engine, "temperature", 75.0, 100);
+        engine.evaluate(100);
+        engine.evaluate(159);
+        assert!(!firing(&engine));
+        engine.evaluate(160);
+        assert!(firing(&engine));
+
+        // The duration starts over once the conditions stop holding.
+        sample(&mut engine, "temperature", 65.0, 170);
+        engine.evaluate(170);
+        sample(&mut engine, "temperature", 75.0, 180);
+        engine.evaluate(180);
+        engine.evaluate(200);
+        assert!(!firing(&engine));
+    }
+
+    #[test]
+    fn window_applies_the_method_to_recent_samples() {
+        let (mut engine, _notifications) = composite(
+            "all",
+            0,
+            serde_json::json!([{
+                "metric": "cpu::usage",
+                "method": "avg",
+                "comparison": "g",
+                "value": 0.5,
+                "window": 60,
+            }]),
+        );
+        sample(&mut engine, "cpu::usage", 0.1, 100);
+        sample(&mut engine, "cpu::usage", 0.7, 130);
+        engine.evaluate(130);
+        assert!(!firing(&engine));
+        // The first sample left the window, only 0.7 and 0.8 are averaged.
+        sample(&mut engine, "cpu::usage", 0.8, 170);
+        engine.evaluate(170);
+        assert!(firing(&engine));
+    }
+}
@@ -0,0 +1,292 @@
+use crate::errors::Error;
+use crate::matcher::AlarmMatcher;
+use chrono::{DateTime, Duration, Local, SecondsFormat};
+use cron::Schedule;
+use ldm_commons::Notification;
+use serde_derive::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// Mutes matching alarms during a time range and/or a recurring window.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct SilenceConfiguration {
+    pub id: Option<String>,
+    #[serde(default)]
+    pub comment: String,
+    #[serde(flatten)]
+    pub matcher: AlarmMatcher,
+    /// RFC 3339, e.g. "2020-06-01T22:00:00+03:00".
+    pub starts_at: Option<String>,
+    pub ends_at: Option<String>,
+    /// Cron expression in local time (sec min hour day month weekday), each match
+    /// opens a window of `duration` seconds.
+    pub schedule: Option<String>,
+    /// Length of a scheduled window. Without a schedule or `ends_at` the silence
+    /// lasts this many seconds from its creation.
+    #[serde(default)]
+    pub duration: u64,
+}
+
+/// A silence as listed by the api.
+#[derive(Serialize, Debug, Clone)]
+pub struct SilenceStatus {
+    #[serde(flatten)]
+    pub silence: SilenceConfiguration,
+    pub active: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Silence {
+    configuration: SilenceConfiguration,
+    starts_at: Option<i64>,
+    ends_at: Option<i64>,
+    schedule: Option<Schedule>,
+}
+
+fn parse_time(value: &Option<String>) -> Result<Option<i64>, Error> {
+    match value {
+        Some(value) => match DateTime::parse_from_rfc3339(value) {
+            Ok(time) => Ok(Some(time.timestamp())),
+            Err(err) => Err(Error::Generic(format!(
+                "Invalid silence time '{}': {}",
+                value, err
+            ))),
+        },
+        None => Ok(None),
+    }
+}
+
+impl Silence {
+    fn new(
+        mut configuration: SilenceConfiguration,
+        now: DateTime<Local>,
+    ) -> Result<Silence, Error> {
+        let schedule = match &configuration.schedule {
+            Some(expression) => match Schedule::from_str(expression) {
+                Ok(schedule) => Some(schedule),
+                Err(err) => {
+                    return Err(Error::Generic(format!(
+                        "Invalid silence schedule '{}': {}",
+                        expression, err
+                    )))
+                }
+            },
+            None => None,
+        };
+        if schedule.is_some() && configuration.duration == 0 {
+            return Err(Error::Generic(String::from(
+                "A scheduled silence needs a duration",
+            )));
+        }
+        if schedule.is_none() && configuration.ends_at.is_none() && configuration.duration > 0 {
+            let ends_at = now + Duration::seconds(configuration.duration as i64);
+            configuration.ends_at = Some(ends_at.to_rfc3339_opts(SecondsFormat::Secs, false));
+        }
+        Ok(Silence {
+            starts_at: parse_time(&configuration.starts_at)?,
+            ends_at: parse_time(&configuration.ends_at)?,
+            schedule,
+            configuration,
+        })
+    }
+
+    fn is_active(&self, now: DateTime<Local>) -> bool {
+        let started = match self.starts_at {
+            Some(start) => now.timestamp() >= start,
+            None => true,
+        };
+        if !started || self.is_expired(now) {
+            return false;
+        }
+        match &self.schedule {
+            Some(schedule) => {
+                let window = Duration::seconds(self.configuration.duration as i64);
+                matches!(schedule.after(&(now - window)).next(), Some(start) if start <= now)
+            }
+            None => true,
+        }
+    }
+
+    fn is_expired(&self, now: DateTime<Local>) -> bool {
+        self.ends_at
+            .map(|end| now.timestamp() >= end)
+            .unwrap_or(false)
+    }
+}
+
+/// Silences shared between the collector and the api.
+#[derive(Debug, Clone, Default)]
+pub struct Silences {
+    silences: Arc<RwLock<Vec<Silence>>>,
+    next_id: Arc<AtomicUsize>,
+}
+
+impl Silences {
+    pub fn new(configurations: Vec<SilenceConfiguration>) -> Silences {
+        let silences = Silences::default();
+        for configuration in configurations {
+            if let Err(err) = silences.add(configuration) {
+                error!("Error occurred while adding silence {}", err);
+            }
+        }
+        silences
+    }
+
+    /// Adds a silence, an id is generated when missing.
+    pub fn add(&self, mut configuration: SilenceConfiguration) -> Result<SilenceStatus, Error> {
+        let now = Local::now();
+        let mut silences = self.silences.write().unwrap();
+        silences.retain(|silence| !silence.is_expired(now));
+        let id = match configuration.id.take() {
+            Some(id) => id,
+            None => format!(
+                "silence-{}",
+                self.next_id.fetch_add(1, Ordering::SeqCst) + 1
+            ),
+        };
+        if silences
+            .iter()
+            .any(|silence| silence.configuration.id.as_deref() == Some(id.as_str()))
+        {
+            return Err(Error::Generic(format!("Silence {} already exists", id)));
+        }
+        configuration.id = Some(id);
+        let silence = Silence::new(configuration, now)?;
+        let status = SilenceStatus {
+            active: silence.is_active(now),
+            silence: silence.configuration.clone(),
+        };
+        silences.push(silence);
+        Ok(status)
+    }
+
+    pub fn remove(&self, id: &str) -> bool {
+        let mut silences = self.silences.write().unwrap();
+        let count = silences.len();
+        silences.retain(|silence| silence.configuration.id.as_deref() != Some(id));
+        silences.len() != count
+    }
+
+    pub fn list(&self) -> Vec<SilenceStatus> {
+        let now = Local::now();
+        let mut silences = self.silences.write().unwrap();
+        silences.retain(|silence| !silence.is_expired(now));
+        silences
+            .iter()
+            .map(|silence| SilenceStatus {
+                silence: silence.configuration.clone(),
+                active: silence.is_active(now),
+            })
+            .collect()
+    }
+
+    /// Id of an active silence matching the notification.
+    pub fn silenced_by(&self, notification: &Notification) -> Option<String> {
+        self.silenced_at(notification, Local::now())
+    }
+
+    /// Id of a silence matching the notification that is active at `now`.
+    pub fn silenced_at(&self, notification: &Notification, now: DateTime<Local>) -> Option<String> {
+        self.silences
+            .read()
+            .unwrap()
+            .iter()
+            .find(|silence| {
+                silence.configuration.matcher.matches(notification) && silence.is_active(now)
+            })
+            .and_then(|silence| silence.configuration.id.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use ldm_commons::AlarmSeverity;
+
+    fn notification(alarm: &str, metric: &str) -> Notification {
+        Notification::new(
+            String::from(alarm),
+            AlarmSeverity::High,
+            String::from("Alarm data set -> 95"),
+            String::from(metric),
+            95.0,
+        )
+    }
+
+    fn silence(config: serde_json::Value) -> SilenceConfiguration {
+        serde_json::from_value(config).unwrap()
+    }
+
+    fn at(hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 6, 3, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn recurring_window_is_active_for_its_duration() {
+        let silences = Silences::new(vec![silence(serde_json::json!({
+            "id": "nightly-backup",
+            "metric": "disk::usage::*",
+            "schedule": "0 0 2 * * *",
+            "duration": 3600,
+        }))]);
+        let backup = notification("Disk is full", "disk::usage::/backup");
+        assert_eq!(silences.silenced_at(&backup, at(1, 59)), None);
+        assert_eq!(
+            silences.silenced_at(&backup, at(2, 30)).as_deref(),
+            Some("nightly-backup")
+        );
+        assert_eq!(silences.silenced_at(&backup, at(3, 0)), None);
+        // The window opens again the next night.
+        let next_night = at(2, 30) + Duration::days(1);
+        assert!(silences.silenced_at(&backup, next_night).is_some());
+    }
+
+    #[test]
+    fn only_matching_alarms_are_silenced() {
+        let silences = Silences::new(vec![silence(serde_json::json!({
+            "id": "maintenance",
+            "alarm": "High CPU*",
+            "starts_at": "2024-06-03T10:00:00Z",
+            "ends_at": "2024-06-03T12:00:00Z",
+        }))]);
+        let now = Utc
+            .with_ymd_and_hms(2024, 6, 3, 11, 0, 0)
+            .unwrap()
+            .with_timezone(&Local);
+        assert!(silences
+            .silenced_at(&notification("High CPU usage", "cpu::usage"), now)
+            .is_some());
+        assert!(silences
+            .silenced_at(&notification("Disk is full", "disk::usage::/"), now)
+            .is_none());
+        let later = now + Duration::hours(2);
+        assert!(silences
+            .silenced_at(&notification("High CPU usage", "cpu::usage"), later)
+            .is_none());
+    }
+
+    #[test]
+    fn silences_are_added_and_removed() {
+        let silences = Silences::default();
+        let added = silences
+            .add(silence(
+                serde_json::json!({"alarm": "High CPU", "duration": 1800}),
+            ))
+            .unwrap();
+        assert!(added.active);
+        assert_eq!(added.silence.id.as_deref(), Some("silence-1"));
+        assert!(added.silence.ends_at.is_some());
+        assert!(silences
+            .add(silence(serde_json::json!({"id": "silence-1"})))
+            .is_err());
+        assert!(silences
+            .add(silence(serde_json::json!({"schedule": "0 0 2 * * *"})))
+            .is_err());
+        assert_eq!(silences.list().len(), 1);
+        assert!(silences.remove("silence-1"));
+        assert!(!silences.remove("silence-1"));
+        assert!(silences.list().is_empty());
+    }
+}
@@ -0,0 +1,338 @@
+use crate::ack::Acknowledgement;
+use crate::core::config::{Alarm, AlarmStatus};
+use chrono::Utc;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct StateConfiguration {
+    /// File that keeps alarm states across restarts.
+    pub path: Option<String>,
+    /// Seconds after which a saved state is too old to be restored.
+    #[serde(default = "default_max_age")]
+    pub max_age: i64,
+    /// Seconds between writes while no alarm changes status, samples of the polls since
+    /// the last write are lost on a crash.
+    #[serde(default = "default_interval")]
+    pub interval: i64,
+}
+
+fn default_max_age() -> i64 {
+    15 * 60
+}
+
+fn default_interval() -> i64 {
+    60
+}
+
+impl Default for StateConfiguration {
+    fn default() -> Self {
+        StateConfiguration {
+            path: None,
+            max_age: default_max_age(),
+            interval: default_interval(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct AlarmState {
+    samples: Vec<f64>,
+    status: AlarmStatus,
+    incident: Option<String>,
+    updated_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    acknowledgement: Option<Acknowledgement>,
+    /// Escalation steps sent for the open incident.
+    #[serde(default)]
+    escalated: usize,
+}
+
+/// Samples, status, open incident, acknowledgement and sent escalation steps of every alarm,
+/// mirrored to a file.
+/// What anomaly and predict conditions learned is not kept, they learn again after a restart.
+#[derive(Debug, Clone, Default)]
+pub struct AlarmStates {
+    path: Option<PathBuf>,
+    interval: i64,
+    states: Arc<Mutex<HashMap<String, AlarmState>>>,
+    /// A status or incident changed since the last write.
+    changed: Arc<AtomicBool>,
+    persisted_at: Arc<AtomicI64>,
+}
+
+impl AlarmStates {
+    /// Reads the saved states, skipping the ones older than `max_age`.
+    pub fn load(config: &StateConfiguration) -> AlarmStates {
+        let path = config.path.as_ref().map(PathBuf::from);
+        let mut states: HashMap<String, AlarmState> = match &path {
+            Some(path) if path.exists() => match File::open(path) {
+                Ok(file) => match serde_json::from_reader(BufReader::new(file)) {
+                    Ok(states) => states,
+                    Err(err) => {
+                        error!("Error while reading alarm states {:?}: {}", path, err);
+                        HashMap::new()
+                    }
+                },
+                Err(err) => {
+                    error!("Error while opening alarm states {:?}: {}", path, err);
+                    HashMap::new()
+                }
+            },
+            _ => HashMap::new(),
+        };
+        let oldest = Utc::now().timestamp() - config.max_age;
+        let saved = states.len();
+        states.retain(|_, state| state.updated_at >= oldest);
+        if saved > states.len() {
+            info!("{} stale alarm state(s) dropped", saved - states.len());
+        }
+        AlarmStates {
+            path,
+            interval: config.interval,
+            states: Arc::new(Mutex::new(states)),
+            changed: Arc::default(),
+            persisted_at: Arc::new(AtomicI64::new(Utc::now().timestamp())),
+        }
+    }
+
+    /// Puts the saved state of the alarm back, returns false when there is none.
+    pub fn restore(&self, key: &str, alarm: &mut Alarm) -> bool {
+        match self.states.lock().unwrap().get(key) {
+            Some(state) => {
+                // The sample size may have shrunk since the states were written.
+                let skip = state
+                    .samples
+                    .len()
+                    .saturating_sub(alarm.config.sample_size());
+                alarm.samples = state.samples[skip..].to_vec();
+                alarm.previous_status = state.status.clone();
+                alarm.incident = state.incident.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn record(&self, key: &str, alarm: &Alarm) {
+        let mut states = self.states.lock().unwrap();
+        let changed = states.get(key).is_none_or(|state| {
+            state.status != alarm.previous_status || state.incident != alarm.incident
+        });
+        if changed {
+            self.changed.store(true, Ordering::SeqCst);
+        }
+        let acknowledgement = states
+            .get(key)
+            .and_then(|state| state.acknowledgement.clone());
+        let escalated = states
+            .get(key)
+            .filter(|state| state.incident == alarm.incident)
+            .map_or(0, |state| state.escalated);
+        states.insert(
+            String::from(key),
+            AlarmState {
+                samples: alarm.samples.clone(),
+                status: alarm.previous_status.clone(),
+                incident: alarm.incident.clone(),
+                updated_at: Utc::now().timestamp(),
+                acknowledgement,
+                escalated,
+            },
+        );
+    }
+
+    /// Escalation steps already sent for `incident` of a recorded alarm.
+    pub fn escalated(&self, key: &str, incident: Option<&str>) -> usize {
+        match self.states.lock().unwrap().get(key) {
+            Some(state) if state.incident.as_deref() == incident => state.escalated,
+            _ => 0,
+        }
+    }
+
+    /// Counts the escalation steps sent for the open incident and writes the states.
+    pub fn escalate(&self, key: &str, sent: usize) {
+        match self.states.lock().unwrap().get_mut(key) {
+            Some(state) => state.escalated = sent,
+            None => return,
+        }
+        self.persist();
+    }
+
+    /// Saved acknowledgements by alarm key.
+    pub fn acknowledgements(&self) -> HashMap<String, Acknowledgement> {
+        self.states
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(key, state)| Some((key.clone(), state.acknowledgement.clone()?)))
+            .collect()
+    }
+
+    /// Sets or, with None, drops the acknowledgement of a recorded alarm and writes the states.
+    pub fn acknowledge(&self, key: &str, acknowledgement: Option<Acknowledgement>) {
+        match self.states.lock().unwrap().get_mut(key) {
+            Some(state) if state.acknowledgement.is_some() || acknowledgement.is_some() => {
+                state.acknowledgement = acknowledgement;
+            }
+            _ => return,
+        }
+        self.persist();
+    }
+
+    /// Writes the states when a status or incident changed, or `interval` seconds passed.
+    pub fn persist_changes(&self) {
+        let elapsed = Utc::now().timestamp() - self.persisted_at.load(Ordering::SeqCst);
+        if self.changed.load(Ordering::SeqCst) || elapsed >= self.interval {
+            self.persist();
+        }
+    }
+
+    /// Writes the states to a temporary file and renames it over the saved ones.
+    pub fn persist(&self) {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+        let states = self.states.lock().unwrap();
+        self.changed.store(false, Ordering::SeqCst);
+        self.persisted_at
+            .store(Utc::now().timestamp(), Ordering::SeqCst);
+        let mut temp = path.clone();
+        temp.set_extension("tmp");
+        let result = File::create(&temp)
+            .map_err(|err| err.to_string())
+            .and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                serde_json::to_writer(&mut writer, &*states).map_err(|err| err.to_string())?;
+                writer.flush().map_err(|err| err.to_string())
+            })
+            .and_then(|_| fs::rename(&temp, path).map_err(|err| err.to_string()));
+        if let Err(err) = result {
+            error!("Error while writing alarm states {:?}: {}", path, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alarm() -> Alarm {
+        Alarm::new(
+            serde_json::from_value(serde_json::json!({
+                "name": "High CPU",
+                "severity": "high",
+                "conditions": [],
+                "sample_size": 2,
+            }))
+            .unwrap(),
+        )
+    }
+
+    fn states(name: &str) -> (AlarmStates, StateConfiguration) {
+        let path =
+            std::env::temp_dir().join(format!("ldm_state_{}_{}.json", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        let config = StateConfiguration {
+            path: Some(path.to_string_lossy().to_string()),
+            ..StateConfiguration::default()
+        };
+        (AlarmStates::load(&config), config)
+    }
+
+    #[test]
+    fn persisted_states_are_restored() {
+        let (states, config) = states("restored");
+        let mut alarm = alarm();
+        alarm.samples = vec![0.5, 0.9];
+        alarm.set_status(AlarmStatus::Alarm);
+        alarm.incident = Some(String::from("cpu::usage@100"));
+        states.record("cpu", &alarm);
+        states.escalate("cpu", 1);
+        assert!(!PathBuf::from(config.path.as_ref().unwrap())
+            .with_extension("tmp")
+            .exists());
+
+        let mut restored = self::alarm();
+        assert!(AlarmStates::load(&config).restore("cpu", &mut restored));
+        assert_eq!(restored.samples, vec![0.5, 0.9]);
+        assert_eq!(restored.previous_status, AlarmStatus::Alarm);
+        assert_eq!(restored.incident.as_deref(), Some("cpu::usage@100"));
+        assert_eq!(
+            AlarmStates::load(&config).escalated("cpu", Some("cpu::usage@100")),
+            1
+        );
+        let _ = fs::remove_file(config.path.unwrap());
+    }
+
+    #[test]
+    fn restored_samples_fit_the_sample_size() {
+        let (states, config) = states("trimmed");
+        let mut alarm = alarm();
+        alarm.samples = vec![0.1, 0.5, 0.9];
+        states.record("cpu", &alarm);
+
+        let mut restored = self::alarm();
+        assert!(states.restore("cpu", &mut restored));
+        assert_eq!(restored.samples, vec![0.5, 0.9]);
+        let _ = fs::remove_file(config.path.unwrap());
+    }
+
+    #[test]
+    fn acknowledgements_are_kept_until_dropped() {
+        let (states, config) = states("acknowledged");
+        let mut alarm = alarm();
+        alarm.set_status(AlarmStatus::Alarm);
+        states.record("cpu", &alarm);
+        states.acknowledge(
+            "cpu",
+            Some(Acknowledgement {
+                alarm: String::from("High CPU"),
+                metric: String::from("cpu::usage"),
+                dimension: None,
+                incident: Some(String::from("cpu@100")),
+                comment: String::from("looking into it"),
+                acknowledged_at: 100,
+                expires_at: None,
+            }),
+        );
+        states.record("cpu", &alarm);
+
+        let restored = AlarmStates::load(&config).acknowledgements();
+        assert_eq!(restored["cpu"].comment, "looking into it");
+        states.acknowledge("cpu", None);
+        assert!(AlarmStates::load(&config).acknowledgements().is_empty());
+        let _ = fs::remove_file(config.path.unwrap());
+    }
+
+    #[test]
+    fn only_status_changes_are_written_before_the_interval() {
+        let (states, config) = states("changes");
+        let path = PathBuf::from(config.path.as_ref().unwrap());
+        let mut alarm = alarm();
+        alarm.set_status(AlarmStatus::Ok);
+        states.record("cpu", &alarm);
+        states.persist_changes();
+        assert!(path.exists());
+
+        alarm.samples = vec![0.5];
+        states.record("cpu", &alarm);
+        states.persist_changes();
+        let mut restored = self::alarm();
+        AlarmStates::load(&config).restore("cpu", &mut restored);
+        assert!(restored.samples.is_empty());
+
+        alarm.set_status(AlarmStatus::Alarm);
+        states.record("cpu", &alarm);
+        states.persist_changes();
+        AlarmStates::load(&config).restore("cpu", &mut restored);
+        assert_eq!(restored.samples, vec![0.5]);
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,61 @@
+use serde_derive::Deserialize;
+
+/// Samples of a `kind = "synthetic"` metric, generated instead of read from the system.
+#[derive(Deserialize, Debug, Clone)]
+pub struct SyntheticConfiguration {
+    #[serde(flatten)]
+    pub shape: Shape,
+    /// Overrides some of the samples of any shape.
+    pub spike: Option<SpikeConfiguration>,
+}
+
+/// Series selected by `shape`. Times are seconds since the metric started, counted in
+/// intervals so the series does not depend on scheduling delays.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "shape")]
+pub enum Shape {
+    #[serde(rename = "constant")]
+    Constant { value: f64 },
+    /// Goes from the middle to `max`, down to `min` and back every `period` seconds.
+    #[serde(rename = "sine")]
+    Sine { min: f64, max: f64, period: u64 },
+    /// Starts at `start` and moves by up to `step` on every sample, kept within `min` and `max`.
+    #[serde(rename = "random_walk")]
+    RandomWalk {
+        start: f64,
+        step: f64,
+        #[serde(default = "default_min")]
+        min: f64,
+        #[serde(default = "default_max")]
+        max: f64,
+        /// Makes the walk repeatable, e.g. for tests.
+        seed: Option<u64>,
+    },
+    /// `before` until `at` seconds, `after` from then on.
+    #[serde(rename = "step")]
+    Step { before: f64, after: f64, at: u64 },
+    /// Last column of each line of a csv file, e.g. a recording, starting over at the end.
+    #[serde(rename = "csv")]
+    Csv { path: String },
+}
+
+fn default_min() -> f64 {
+    f64::NEG_INFINITY
+}
+
+fn default_max() -> f64 {
+    f64::INFINITY
+}
+
+/// The last `length` samples of every `every` samples read `value`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct SpikeConfiguration {
+    pub every: u64,
+    #[serde(default = "default_length")]
+    pub length: u64,
+    pub value: f64,
+}
+
+fn default_length() -> u64 {
+    1
+}
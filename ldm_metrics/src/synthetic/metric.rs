@@ -0,0 +1,286 @@
+use crate::core::config::{Alarm, Metric, MetricConfiguration, SampleCollectError};
+use crate::errors::Error;
+use crate::synthetic::config::{Shape, SyntheticConfiguration};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::borrow::BorrowMut;
+use std::f64::consts::PI;
+use std::fs;
+
+#[derive(Debug)]
+pub struct SyntheticMetric {
+    name: String,
+    dimension: Option<String>,
+    interval: u64,
+    alarms: Vec<Alarm>,
+    config: SyntheticConfiguration,
+    /// Samples generated so far.
+    count: u64,
+    /// Current value of a random walk.
+    walk: f64,
+    rng: StdRng,
+    /// Values of a csv shape.
+    values: Vec<f64>,
+}
+
+impl SyntheticMetric {
+    pub fn new(config: MetricConfiguration) -> Result<SyntheticMetric, Error> {
+        let synthetic = match config.synthetic {
+            Some(synthetic) => synthetic,
+            None => {
+                return Err(Error::Generic(format!(
+                    "Synthetic metric {} needs a [metrics.synthetic] shape",
+                    config.name
+                )))
+            }
+        };
+        let name = &config.name;
+        let invalid =
+            |reason: &str| Error::Generic(format!("Synthetic metric {} {}", name, reason));
+        let (mut walk, mut seed, mut values) = (0.0, None, Vec::new());
+        match &synthetic.shape {
+            Shape::Sine { period: 0, .. } => return Err(invalid("needs a period")),
+            Shape::RandomWalk {
+                start,
+                seed: walk_seed,
+                ..
+            } => {
+                walk = *start;
+                seed = *walk_seed;
+            }
+            Shape::Csv { path } => {
+                values = read_values(path)?;
+                if values.is_empty() {
+                    return Err(invalid(&format!("has no values in {}", path)));
+                }
+            }
+            _ => {}
+        }
+        if let Some(spike) = &synthetic.spike {
+            if spike.every == 0 {
+                return Err(invalid("needs a spike every > 0 samples"));
+            }
+        }
+        Ok(SyntheticMetric {
+            name: config.name,
+            dimension: config.dimension,
+            interval: config.interval,
+            alarms: Alarm::from(config.alarms),
+            config: synthetic,
+            count: 0,
+            walk,
+            rng: StdRng::seed_from_u64(seed.unwrap_or_else(rand::random)),
+            values,
+        })
+    }
+
+    fn next_value(&mut self, index: u64, elapsed: u64) -> f64 {
+        match &self.config.shape {
+            Shape::Constant { value } => *value,
+            Shape::Sine { min, max, period } => {
+                let phase = 2.0 * PI * (elapsed % period) as f64 / *period as f64;
+                min + (max - min) * (1.0 + phase.sin()) / 2.0
+            }
+            Shape::RandomWalk { step, min, max, .. } => {
+                let value = self.walk;
+                let delta = (self.rng.gen::<f64>() * 2.0 - 1.0) * step;
+                self.walk = (self.walk + delta).max(*min).min(*max);
+                value
+            }
+            Shape::Step { before, after, at } => {
+                if elapsed < *at {
+                    *before
+                } else {
+                    *after
+                }
+            }
+            Shape::Csv { .. } => self.values[(index % self.values.len() as u64) as usize],
+        }
+    }
+}
+
+/// Last column of every line, a first line that is not a number is taken as the header.
+/// NaN and infinite values are rejected.
+fn read_values(path: &str) -> Result<Vec<f64>, Error> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| Error::Generic(format!("Error while reading {}: {}", path, err)))?;
+    let mut values = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let field = match line.rsplit(',').next().map(str::trim) {
+            Some(field) if !field.is_empty() => field,
+            _ => continue,
+        };
+        match field.parse::<f64>() {
+            // A NaN sample panics when a condition compares samples, e.g. max.
+            Ok(value) if value.is_finite() => values.push(value),
+            Err(_) if index == 0 => {}
+            _ => {
+                return Err(Error::Generic(format!(
+                    "Invalid value {} on line {} of {}",
+                    field,
+                    index + 1,
+                    path
+                )))
+            }
+        }
+    }
+    Ok(values)
+}
+
+impl Metric for SyntheticMetric {
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_dimension(&self) -> Option<String> {
+        self.dimension.clone()
+    }
+
+    fn poll_metric(&mut self) -> Result<f64, SampleCollectError> {
+        let index = self.count;
+        let elapsed = index * self.get_period() as u64;
+        self.count += 1;
+        let value = self.next_value(index, elapsed);
+        match &self.config.spike {
+            Some(spike) if index % spike.every >= spike.every.saturating_sub(spike.length) => {
+                Ok(spike.value)
+            }
+            _ => Ok(value),
+        }
+    }
+
+    fn get_alarms(&mut self) -> &mut [Alarm] {
+        self.alarms.borrow_mut()
+    }
+
+    fn get_period(&self) -> u32 {
+        (self.interval * 60) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn synthetic(synthetic: serde_json::Value) -> Result<SyntheticMetric, Error> {
+        SyntheticMetric::new(
+            serde_json::from_value(serde_json::json!({
+                "name": "synthetic::load",
+                "interval": 1,
+                "kind": "synthetic",
+                "alarms": [],
+                "synthetic": synthetic,
+            }))
+            .unwrap(),
+        )
+    }
+
+    fn poll(metric: &mut SyntheticMetric, count: usize) -> Vec<f64> {
+        (0..count).map(|_| metric.poll_metric().unwrap()).collect()
+    }
+
+    /// Writes `content` to a temporary csv file, removed by the caller.
+    fn recording(name: &str, content: &str) -> String {
+        let path =
+            std::env::temp_dir().join(format!("ldm_synthetic_{}_{}.csv", name, std::process::id()));
+        fs::write(&path, content).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn seeded_random_walks_repeat() {
+        let walk = serde_json::json!({
+            "shape": "random_walk",
+            "start": 50.0,
+            "step": 5.0,
+            "min": 40.0,
+            "max": 60.0,
+            "seed": 7,
+        });
+        let values = poll(&mut synthetic(walk.clone()).unwrap(), 50);
+        assert_eq!(values, poll(&mut synthetic(walk).unwrap(), 50));
+        assert_eq!(values[0], 50.0);
+        assert!(values.iter().all(|value| (40.0..=60.0).contains(value)));
+        assert!(values
+            .windows(2)
+            .all(|pair| (pair[1] - pair[0]).abs() <= 5.0));
+    }
+
+    #[test]
+    fn sine_goes_up_first() {
+        // One sample every 60 seconds, four per period.
+        let mut metric = synthetic(serde_json::json!({
+            "shape": "sine",
+            "min": 0.0,
+            "max": 10.0,
+            "period": 240,
+        }))
+        .unwrap();
+        let values: Vec<f64> = poll(&mut metric, 5)
+            .iter()
+            .map(|value| value.round())
+            .collect();
+        assert_eq!(values, vec![5.0, 10.0, 5.0, 0.0, 5.0]);
+        assert!(synthetic(serde_json::json!({
+            "shape": "sine",
+            "min": 0.0,
+            "max": 10.0,
+            "period": 0,
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn step_changes_at_its_time() {
+        let mut metric = synthetic(serde_json::json!({
+            "shape": "step",
+            "before": 1.0,
+            "after": 2.0,
+            "at": 120,
+        }))
+        .unwrap();
+        assert_eq!(poll(&mut metric, 4), vec![1.0, 1.0, 2.0, 2.0]);
+    }
+
+    #[test]
+    fn spikes_override_the_shape() {
+        let mut metric = synthetic(serde_json::json!({
+            "shape": "constant",
+            "value": 1.0,
+            "spike": {"every": 3, "value": 9.0},
+        }))
+        .unwrap();
+        assert_eq!(poll(&mut metric, 6), vec![1.0, 1.0, 9.0, 1.0, 1.0, 9.0]);
+        assert!(synthetic(serde_json::json!({
+            "shape": "constant",
+            "value": 1.0,
+            "spike": {"every": 0, "value": 9.0},
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn csv_values_start_over_at_the_end() {
+        let path = recording("loop", "time,value\n100,1\n160,2\n\n220,3\n");
+        let mut metric = synthetic(serde_json::json!({"shape": "csv", "path": path})).unwrap();
+        assert_eq!(poll(&mut metric, 5), vec![1.0, 2.0, 3.0, 1.0, 2.0]);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn csv_values_must_be_finite() {
+        for (name, content) in [
+            ("nan", "1\nNaN\n"),
+            ("inf", "1\ninf\n"),
+            ("first", "NaN\n1\n"),
+        ] {
+            let path = recording(name, content);
+            let err = synthetic(serde_json::json!({"shape": "csv", "path": path})).unwrap_err();
+            assert!(err.to_string().starts_with("Invalid value"), "{}", err);
+            let _ = fs::remove_file(path);
+        }
+        let path = recording("empty", "value\n");
+        assert!(synthetic(serde_json::json!({"shape": "csv", "path": path})).is_err());
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,157 @@
+use crate::system::{MemoryInfo, MountInfo, NetworkInfo, SystemInfo};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Reads a recorded tree laid out like the root filesystem, e.g. `proc/meminfo` or
+/// `sys/class/net/eth0/statistics/rx_bytes`. After the n-th `wait` a file is read from
+/// `<file>.<n>` when it exists, e.g. `proc/stat.1`, so counters can advance between reads.
+/// Mount sizes are not files, they are listed in `statvfs` as `<mount> <total> <avail>` bytes,
+/// with the mount escaped as in `proc/mounts`.
+#[derive(Debug)]
+pub struct FixtureSystem {
+    root: PathBuf,
+    waits: AtomicUsize,
+}
+
+impl FixtureSystem {
+    pub fn new<P: AsRef<Path>>(root: P) -> FixtureSystem {
+        FixtureSystem {
+            root: root.as_ref().to_path_buf(),
+            waits: AtomicUsize::new(0),
+        }
+    }
+
+    fn read(&self, file: &str) -> io::Result<String> {
+        let waits = self.waits.load(Ordering::SeqCst);
+        if waits > 0 {
+            let advanced = self.root.join(format!("{}.{}", file, waits));
+            if advanced.exists() {
+                return fs::read_to_string(advanced);
+            }
+        }
+        fs::read_to_string(self.root.join(file))
+    }
+
+    fn read_number(&self, file: &str) -> io::Result<u64> {
+        let content = self.read(file)?;
+        content
+            .trim()
+            .parse()
+            .map_err(|_| invalid(file, content.trim()))
+    }
+
+    /// Cpu time of the aggregate `cpu` line, as (user, total) jiffies.
+    fn cpu_times(&self) -> io::Result<(u64, u64)> {
+        let content = self.read("proc/stat")?;
+        let line = content
+            .lines()
+            .find(|line| line.starts_with("cpu "))
+            .ok_or_else(|| invalid("proc/stat", "no cpu line"))?;
+        let times = line
+            .split_whitespace()
+            .skip(1)
+            .take(6)
+            .map(|time| time.parse::<u64>().map_err(|_| invalid("proc/stat", line)))
+            .collect::<io::Result<Vec<u64>>>()?;
+        if times.len() < 6 {
+            return Err(invalid("proc/stat", line));
+        }
+        Ok((times[0], times.iter().sum()))
+    }
+}
+
+fn invalid(file: &str, content: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid {}: {}", file, content),
+    )
+}
+
+impl SystemInfo for FixtureSystem {
+    fn cpu_user(&self, window: Duration) -> io::Result<f64> {
+        let (user, total) = self.cpu_times()?;
+        self.wait(window);
+        let (next_user, next_total) = self.cpu_times()?;
+        let total = next_total.saturating_sub(total);
+        if total == 0 {
+            return Ok(0.0);
+        }
+        Ok(next_user.saturating_sub(user) as f64 / total as f64)
+    }
+
+    fn memory(&self) -> io::Result<MemoryInfo> {
+        let content = self.read("proc/meminfo")?;
+        let mut fields = HashMap::new();
+        for line in content.lines() {
+            let mut parts = line.split_whitespace();
+            let (name, value) = match (parts.next(), parts.next()) {
+                (Some(name), Some(value)) => (name.trim_end_matches(':'), value),
+                _ => continue,
+            };
+            let value = value
+                .parse::<u64>()
+                .map_err(|_| invalid("proc/meminfo", line))?;
+            let unit = if parts.next() == Some("kB") { 1024 } else { 1 };
+            fields.insert(name, value * unit);
+        }
+        let field = |name: &str| fields.get(name).copied().unwrap_or(0);
+        let total = fields
+            .get("MemTotal")
+            .copied()
+            .ok_or_else(|| invalid("proc/meminfo", "no MemTotal"))?;
+        let free = (field("MemFree") + field("Buffers") + field("Cached") + field("SReclaimable"))
+            .saturating_sub(field("Shmem"));
+        Ok(MemoryInfo { total, free })
+    }
+
+    fn mounts(&self) -> io::Result<Vec<MountInfo>> {
+        let sizes = self.read("statvfs")?;
+        let sizes: HashMap<&str, (u64, u64)> = sizes
+            .lines()
+            .filter_map(|line| {
+                let parts: Vec<&str> = line.split_whitespace().collect();
+                match parts.as_slice() {
+                    [mount, total, avail] => {
+                        Some((*mount, (total.parse().ok()?, avail.parse().ok()?)))
+                    }
+                    _ => None,
+                }
+            })
+            .collect();
+        let mounts = self.read("proc/mounts")?;
+        Ok(mounts
+            .lines()
+            .filter_map(|line| line.split_whitespace().nth(1))
+            .filter_map(|mount| {
+                let (total, avail) = *sizes.get(mount)?;
+                Some(MountInfo {
+                    mounted_on: mount.replace("\\040", " "),
+                    total,
+                    avail,
+                })
+            })
+            .collect())
+    }
+
+    fn network(&self, interface: &str) -> io::Result<NetworkInfo> {
+        let statistics = format!("sys/class/net/{}/statistics", interface);
+        Ok(NetworkInfo {
+            rx_bytes: self.read_number(&format!("{}/rx_bytes", statistics))?,
+            tx_bytes: self.read_number(&format!("{}/tx_bytes", statistics))?,
+        })
+    }
+
+    fn cpu_temp(&self) -> io::Result<f64> {
+        self.read_number("sys/class/thermal/thermal_zone0/temp")
+            .or_else(|_| self.read_number("sys/class/hwmon/hwmon0/temp1_input"))
+            .map(|millis| millis as f64 / 1000.0)
+    }
+
+    fn wait(&self, _duration: Duration) {
+        self.waits.fetch_add(1, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,102 @@
+pub mod fixture;
+
+use std::fmt::Debug;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+use systemstat::{Platform, System};
+
+/// Memory in bytes, reclaimable caches count as free.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryInfo {
+    pub total: u64,
+    pub free: u64,
+}
+
+/// Size of a mounted filesystem in bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MountInfo {
+    pub mounted_on: String,
+    pub total: u64,
+    pub avail: u64,
+}
+
+/// Bytes received and sent by an interface since boot.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NetworkInfo {
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+/// What the system metrics read, `HostSystem` reads the running host and `FixtureSystem`
+/// a recorded `/proc` and `/sys` tree. Metrics read the host unless they are built with
+/// `new_with_system`.
+pub trait SystemInfo: Debug + Send + Sync {
+    /// Share of the cpu time spent in user mode over `window`, from 0 to 1.
+    fn cpu_user(&self, window: Duration) -> io::Result<f64>;
+
+    fn memory(&self) -> io::Result<MemoryInfo>;
+
+    fn mounts(&self) -> io::Result<Vec<MountInfo>>;
+
+    fn network(&self, interface: &str) -> io::Result<NetworkInfo>;
+
+    /// Cpu temperature in celsius.
+    fn cpu_temp(&self) -> io::Result<f64>;
+
+    /// Waits between two reads of a counter.
+    fn wait(&self, duration: Duration);
+}
+
+/// The running host, read through `systemstat`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HostSystem;
+
+pub fn host() -> Arc<dyn SystemInfo> {
+    Arc::new(HostSystem)
+}
+
+impl SystemInfo for HostSystem {
+    fn cpu_user(&self, window: Duration) -> io::Result<f64> {
+        let load = System::new().cpu_load_aggregate()?;
+        self.wait(window);
+        Ok(load.done()?.user as f64)
+    }
+
+    fn memory(&self) -> io::Result<MemoryInfo> {
+        System::new().memory().map(|memory| MemoryInfo {
+            total: memory.total.as_u64(),
+            free: memory.free.as_u64(),
+        })
+    }
+
+    fn mounts(&self) -> io::Result<Vec<MountInfo>> {
+        System::new().mounts().map(|mounts| {
+            mounts
+                .into_iter()
+                .map(|mount| MountInfo {
+                    mounted_on: mount.fs_mounted_on,
+                    total: mount.total.as_u64(),
+                    avail: mount.avail.as_u64(),
+                })
+                .collect()
+        })
+    }
+
+    fn network(&self, interface: &str) -> io::Result<NetworkInfo> {
+        System::new()
+            .network_stats(interface)
+            .map(|stats| NetworkInfo {
+                rx_bytes: stats.rx_bytes.as_u64(),
+                tx_bytes: stats.tx_bytes.as_u64(),
+            })
+    }
+
+    fn cpu_temp(&self) -> io::Result<f64> {
+        System::new().cpu_temp().map(f64::from)
+    }
+
+    fn wait(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
@@ -1,18 +1,28 @@
 use crate::core::config::{Alarm, Metric, MetricConfiguration, SampleCollectError};
+use crate::system::{host, SystemInfo};
 use std::borrow::BorrowMut;
-use systemstat::{Platform, System};
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub struct TemperatureMetric {
     interval: u64,
     alarms: Vec<Alarm>,
+    system: Arc<dyn SystemInfo>,
 }
 
 impl TemperatureMetric {
     pub fn new(config: MetricConfiguration) -> TemperatureMetric {
+        TemperatureMetric::new_with_system(config, host())
+    }
+
+    pub fn new_with_system(
+        config: MetricConfiguration,
+        system: Arc<dyn SystemInfo>,
+    ) -> TemperatureMetric {
         TemperatureMetric {
             interval: config.interval,
             alarms: Alarm::from(config.alarms),
+            system,
         }
     }
 }
@@ -23,8 +33,8 @@ impl Metric for TemperatureMetric {
     }
 
     fn poll_metric(&mut self) -> Result<f64, SampleCollectError> {
-        match System::new().cpu_temp() {
-            Ok(temp) => Ok(temp as f64),
+        match self.system.cpu_temp() {
+            Ok(temp) => Ok(temp),
             Err(err) => Err(SampleCollectError::new(format!(
                 "Error while gathering info for Temperature: {}",
                 err
@@ -0,0 +1,151 @@
+use ldm_metrics::core::config::{Metric, MetricConfiguration};
+use ldm_metrics::cpu::metric::CpuUsageMetric;
+use ldm_metrics::disk::metric::DiskUsageMetric;
+use ldm_metrics::mem::metric::MemoryUsageMetric;
+use ldm_metrics::network::metric::{
+    NetworkRxTotalMetric, NetworkRxUsageMetric, NetworkTxTotalMetric, NetworkTxUsageMetric,
+};
+use ldm_metrics::system::fixture::FixtureSystem;
+use ldm_metrics::system::SystemInfo;
+use ldm_metrics::temp::metric::TemperatureMetric;
+use std::sync::Arc;
+
+/// A fresh fixture per metric, `wait` advances the fixture it is called on.
+fn fixture(name: &str) -> Arc<dyn SystemInfo> {
+    Arc::new(FixtureSystem::new(format!(
+        "{}/tests/fixtures/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    )))
+}
+
+fn config(name: &str, dimension: Option<&str>) -> MetricConfiguration {
+    serde_json::from_value(serde_json::json!({
+        "name": name,
+        "dimension": dimension,
+        "alarms": [],
+        "interval": 1,
+    }))
+    .unwrap()
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-9,
+        "expected {}, got {}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn cpu_usage_is_user_share_between_reads() {
+    let mut metric = CpuUsageMetric::new_with_system(config("cpu::usage", None), fixture("host"));
+    assert_close(metric.poll_metric().unwrap(), 250.0 / 480.0);
+}
+
+#[test]
+fn memory_usage_counts_caches_as_free() {
+    let mut metric =
+        MemoryUsageMetric::new_with_system(config("memory::usage", None), fixture("host"));
+    assert_close(metric.poll_metric().unwrap(), 65.0);
+}
+
+#[test]
+fn disk_usage_of_mount() {
+    let mut metric =
+        DiskUsageMetric::new_with_system(config("disk::usage", Some("/")), fixture("host"));
+    assert_close(metric.poll_metric().unwrap(), 75.0);
+    let mut metric = DiskUsageMetric::new_with_system(
+        config("disk::usage", Some("/mnt/backup disk")),
+        fixture("host"),
+    );
+    assert_close(metric.poll_metric().unwrap(), 10.0);
+}
+
+#[test]
+fn disk_usage_of_unknown_mount_fails() {
+    let mut metric =
+        DiskUsageMetric::new_with_system(config("disk::usage", Some("/data")), fixture("host"));
+    let err = metric.poll_metric().unwrap_err();
+    assert_eq!(err.to_string(), "Given mount path is not valid");
+}
+
+#[test]
+fn network_usage_is_kilobytes_between_reads() {
+    let mut rx = NetworkRxUsageMetric::new_with_system(
+        config("network::rx::usage", Some("eth0")),
+        fixture("host"),
+    );
+    assert_close(rx.poll_metric().unwrap(), 250.0);
+    let mut tx = NetworkTxUsageMetric::new_with_system(
+        config("network::tx::usage", Some("eth0")),
+        fixture("host"),
+    );
+    assert_close(tx.poll_metric().unwrap(), 40.0);
+}
+
+#[test]
+fn network_total_is_kilobytes_since_boot() {
+    let mut rx = NetworkRxTotalMetric::new_with_system(
+        config("network::rx::total", Some("eth0")),
+        fixture("host"),
+    );
+    assert_close(rx.poll_metric().unwrap(), 5000.0);
+    let mut tx = NetworkTxTotalMetric::new_with_system(
+        config("network::tx::total", Some("eth0")),
+        fixture("host"),
+    );
+    assert_close(tx.poll_metric().unwrap(), 2000.0);
+}
+
+#[test]
+fn network_of_unknown_interface_fails() {
+    let mut metric = NetworkRxTotalMetric::new_with_system(
+        config("network::rx::total", Some("wlan0")),
+        fixture("host"),
+    );
+    let err = metric.poll_metric().unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("Error while gathering info for Network"));
+}
+
+#[test]
+fn temperature_of_thermal_zone() {
+    let mut metric =
+        TemperatureMetric::new_with_system(config("temperature", None), fixture("host"));
+    assert_close(metric.poll_metric().unwrap(), 48.5);
+}
+
+#[test]
+fn temperature_falls_back_to_hwmon() {
+    let mut metric =
+        TemperatureMetric::new_with_system(config("temperature", None), fixture("hwmon"));
+    assert_close(metric.poll_metric().unwrap(), 61.25);
+}
+
+#[test]
+fn missing_files_are_collect_errors() {
+    let mut metrics: Vec<Box<dyn Metric>> = vec![
+        Box::new(CpuUsageMetric::new_with_system(
+            config("cpu::usage", None),
+            fixture("hwmon"),
+        )),
+        Box::new(MemoryUsageMetric::new_with_system(
+            config("memory::usage", None),
+            fixture("hwmon"),
+        )),
+        Box::new(DiskUsageMetric::new_with_system(
+            config("disk::usage", Some("/")),
+            fixture("hwmon"),
+        )),
+        Box::new(TemperatureMetric::new_with_system(
+            config("temperature", None),
+            fixture("missing"),
+        )),
+    ];
+    for metric in metrics.iter_mut() {
+        assert!(metric.poll_metric().is_err(), "{}", metric.get_name());
+    }
+}
@@ -0,0 +1,125 @@
+use crate::queue::now;
+use ldm_commons::Notification;
+use std::collections::HashMap;
+
+/// Seconds an incident is remembered after its last notification.
+const KEEP: u64 = 24 * 60 * 60;
+
+/// What every sender got per incident, so an acknowledgement reaches each alert of it, also
+/// the ones opened by an escalation step or a digest.
+#[derive(Debug, Default)]
+pub struct OpenAlerts {
+    /// Sender names and the notifications they got by incident id, with the last send time.
+    incidents: HashMap<String, (u64, Vec<(String, Notification)>)>,
+    /// Incidents waiting in the digest of each sender.
+    digested: HashMap<String, Vec<String>>,
+}
+
+impl OpenAlerts {
+    /// `sender` got `notification` on its own.
+    pub fn sent(&mut self, sender: &str, notification: &Notification) {
+        if let Some(incident) = &notification.context.incident {
+            self.open(incident.clone(), sender, notification);
+        }
+    }
+
+    /// `notification` waits in the digest of `sender`.
+    pub fn digested(&mut self, sender: &str, notification: &Notification) {
+        if let Some(incident) = &notification.context.incident {
+            self.digested
+                .entry(String::from(sender))
+                .or_default()
+                .push(incident.clone());
+        }
+    }
+
+    /// The digest of `sender` went out as `digest`, which is acknowledged with the first of
+    /// its alarms.
+    pub fn flushed(&mut self, sender: &str, digest: &Notification) {
+        for incident in self.digested.remove(sender).unwrap_or_default() {
+            self.open(incident, sender, digest);
+        }
+    }
+
+    /// Takes the alerts of the incident of `notification`, each sender and alert once.
+    pub fn acknowledge(&mut self, notification: &Notification) -> Vec<(String, Notification)> {
+        notification
+            .context
+            .incident
+            .as_ref()
+            .and_then(|incident| self.incidents.remove(incident))
+            .map(|(_, alerts)| alerts)
+            .unwrap_or_default()
+    }
+
+    fn open(&mut self, incident: String, sender: &str, notification: &Notification) {
+        let oldest = now().saturating_sub(KEEP);
+        self.incidents.retain(|_, (sent_at, _)| *sent_at >= oldest);
+        let (sent_at, alerts) = self.incidents.entry(incident).or_default();
+        *sent_at = now();
+        // Later notifications of an incident update the same alert.
+        alerts.retain(|(name, sent)| {
+            name != sender || sent.context.incident != notification.context.incident
+        });
+        alerts.push((String::from(sender), notification.clone()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ldm_commons::{AlarmContext, AlarmSeverity};
+
+    fn notification(incident: &str) -> Notification {
+        Notification::new(
+            String::from("High CPU"),
+            AlarmSeverity::High,
+            String::from("Alarm data set -> 0.9"),
+            String::from("cpu::usage"),
+            0.9,
+        )
+        .context(AlarmContext {
+            incident: Some(String::from(incident)),
+            ..AlarmContext::default()
+        })
+    }
+
+    fn senders(alerts: &[(String, Notification)]) -> Vec<&str> {
+        alerts.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    #[test]
+    fn every_sender_of_the_incident_is_acknowledged_once() {
+        let mut alerts = OpenAlerts::default();
+        let firing = notification("cpu@100");
+        alerts.sent("slack", &firing);
+        alerts.sent("slack", &firing);
+        // Sent by an escalation step.
+        alerts.sent("oncall", &firing);
+        alerts.sent("slack", &notification("disk@100"));
+
+        assert_eq!(
+            senders(&alerts.acknowledge(&firing)),
+            vec!["slack", "oncall"]
+        );
+        assert!(alerts.acknowledge(&firing).is_empty());
+        assert_eq!(alerts.acknowledge(&notification("disk@100")).len(), 1);
+    }
+
+    #[test]
+    fn digested_incidents_acknowledge_the_digest() {
+        let mut alerts = OpenAlerts::default();
+        alerts.digested("opsgenie", &notification("cpu@100"));
+        alerts.digested("opsgenie", &notification("disk@100"));
+        // Nothing was sent before the digest goes out.
+        assert!(alerts.acknowledge(&notification("cpu@100")).is_empty());
+
+        alerts.flushed("opsgenie", &notification("digest@100"));
+        let acknowledged = alerts.acknowledge(&notification("cpu@100"));
+        assert_eq!(senders(&acknowledged), vec!["opsgenie"]);
+        assert_eq!(
+            acknowledged[0].1.context.incident.as_deref(),
+            Some("digest@100")
+        );
+    }
+}
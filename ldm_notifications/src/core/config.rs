@@ -1,18 +1,145 @@
+use crate::errors::Error;
+use crate::exec::config::ExecConfiguration;
+use crate::journal::config::JournalSenderConfiguration;
 use crate::opsgenie::config::OpsgenieConfiguration;
 use crate::slack::config::SlackConfiguration;
-use ldm_commons::Notification;
-use serde::Deserialize;
+use crate::syslog::config::SyslogSenderConfiguration;
+use ldm_commons::template::TemplateConfiguration;
+use ldm_commons::{AlarmSeverity, Notification};
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
 use std::fmt::Debug;
 
+pub type SendError = Error;
+
+#[derive(Deserialize, Debug)]
+pub struct NotificationConfiguration {
+    /// Name used by alarm `notify` lists and routes and as key of queued retries, defaults to
+    /// `<kind>#<index>`. Required while the delivery queue is enabled.
+    pub id: Option<String>,
+    #[serde(flatten)]
+    pub sender: SenderConfiguration,
+    #[serde(default)]
+    pub retry: RetryConfiguration,
+    pub rate_limit: Option<RateLimitConfiguration>,
+    pub template: Option<TemplateConfiguration>,
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(tag = "kind")]
-pub enum NotificationConfiguration {
+pub enum SenderConfiguration {
     #[serde(rename = "opsgenie")]
     OpsGenie(OpsgenieConfiguration),
     #[serde(rename = "slack")]
     Slack(SlackConfiguration),
+    #[serde(rename = "exec")]
+    Exec(ExecConfiguration),
+    #[serde(rename = "syslog")]
+    Syslog(SyslogSenderConfiguration),
+    #[serde(rename = "journal")]
+    Journal(JournalSenderConfiguration),
+}
+
+impl SenderConfiguration {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SenderConfiguration::OpsGenie(_) => "opsgenie",
+            SenderConfiguration::Slack(_) => "slack",
+            SenderConfiguration::Exec(_) => "exec",
+            SenderConfiguration::Syslog(_) => "syslog",
+            SenderConfiguration::Journal(_) => "journal",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RetryConfiguration {
+    /// Number of retries after the first failed attempt.
+    #[serde(default = "default_attempts")]
+    pub attempts: u32,
+    /// Seconds to wait before the first retry, doubled on every further retry.
+    #[serde(default = "default_backoff")]
+    pub backoff: u64,
+    #[serde(default = "default_max_backoff")]
+    pub max_backoff: u64,
+    /// Seconds after which an undelivered notification is dropped.
+    #[serde(default = "default_max_age")]
+    pub max_age: u64,
+}
+
+fn default_attempts() -> u32 {
+    5
+}
+
+fn default_backoff() -> u64 {
+    5
+}
+
+fn default_max_backoff() -> u64 {
+    300
+}
+
+fn default_max_age() -> u64 {
+    3600
+}
+
+impl Default for RetryConfiguration {
+    fn default() -> Self {
+        RetryConfiguration {
+            attempts: default_attempts(),
+            backoff: default_backoff(),
+            max_backoff: default_max_backoff(),
+            max_age: default_max_age(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct DeliveryConfiguration {
+    /// File that keeps pending notifications across restarts.
+    pub queue: Option<String>,
+    /// Sender ids per severity, used for alarms without a `notify` list.
+    #[serde(default)]
+    pub routes: HashMap<AlarmSeverity, Vec<String>>,
+    pub digest: Option<DigestConfiguration>,
+    /// Limit shared by all senders.
+    pub rate_limit: Option<RateLimitConfiguration>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RateLimitConfiguration {
+    /// Messages allowed within `period`, at least one.
+    #[serde(deserialize_with = "at_least_one")]
+    pub messages: u32,
+    /// Seconds.
+    pub period: u64,
+}
+
+/// A limit of zero messages would never let anything through.
+fn at_least_one<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let messages = u32::deserialize(deserializer)?;
+    if messages < 1 {
+        return Err(serde::de::Error::custom(
+            "rate_limit messages must be at least 1",
+        ));
+    }
+    Ok(messages)
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DigestConfiguration {
+    /// Seconds to collect notifications before sending them as one message.
+    pub window: u64,
+    /// Severities that are sent right away instead of waiting for the digest.
+    #[serde(default)]
+    pub bypass: Vec<AlarmSeverity>,
 }
 
 pub trait Sender: Debug + Send + Sync {
-    fn send(&self, notification: &Notification);
+    fn send(&self, notification: &Notification) -> Result<(), SendError>;
+
+    /// Acknowledges the alert opened by `notification`, senders without alert state ignore it.
+    fn acknowledge(&self, _notification: &Notification, _comment: &str) -> Result<(), SendError> {
+        Ok(())
+    }
 }
@@ -0,0 +1,113 @@
+use crate::core::config::DigestConfiguration;
+use crate::queue::now;
+use ldm_commons::{AlarmContext, AlarmSeverity, Notification};
+use std::collections::HashMap;
+
+#[derive(Debug)]
+struct Batch {
+    opened_at: u64,
+    notifications: Vec<Notification>,
+}
+
+/// Collects notifications per sender and releases them as one message once the window is over.
+#[derive(Debug)]
+pub struct DigestBuffer {
+    window: u64,
+    bypass: Vec<AlarmSeverity>,
+    batches: HashMap<String, Batch>,
+}
+
+impl DigestBuffer {
+    pub fn new(conf: &DigestConfiguration) -> DigestBuffer {
+        DigestBuffer {
+            window: conf.window,
+            bypass: conf.bypass.clone(),
+            batches: HashMap::new(),
+        }
+    }
+
+    pub fn bypasses(&self, notification: &Notification) -> bool {
+        self.bypass.contains(&notification.severity)
+    }
+
+    pub fn add(&mut self, sender: &str, notification: Notification) {
+        self.batches
+            .entry(String::from(sender))
+            .or_insert_with(|| Batch {
+                opened_at: now(),
+                notifications: Vec::new(),
+            })
+            .notifications
+            .push(notification);
+    }
+
+    /// Seconds until the oldest batch is due.
+    pub fn next_due(&self) -> Option<u64> {
+        let now = now();
+        self.batches
+            .values()
+            .map(|batch| (batch.opened_at + self.window).saturating_sub(now))
+            .min()
+    }
+
+    pub fn take_due(&mut self) -> Vec<(String, Notification)> {
+        let now = now();
+        let window = self.window;
+        let due: Vec<String> = self
+            .batches
+            .iter()
+            .filter(|(_, batch)| batch.opened_at + window <= now)
+            .map(|(sender, _)| sender.clone())
+            .collect();
+        due.into_iter()
+            .filter_map(|sender| self.batches.remove(&sender).map(|batch| (sender, batch)))
+            .map(|(sender, batch)| (sender, group(batch.notifications)))
+            .collect()
+    }
+
+    pub fn take_all(&mut self) -> Vec<(String, Notification)> {
+        self.batches
+            .drain()
+            .map(|(sender, batch)| (sender, group(batch.notifications)))
+            .collect()
+    }
+}
+
+/// Builds one notification out of a batch, `value` of the result is the number of alarms.
+/// Its incident is taken from the first timestamp, so each digest gets its own Opsgenie alert.
+fn group(mut notifications: Vec<Notification>) -> Notification {
+    if notifications.len() == 1 {
+        return notifications.remove(0);
+    }
+    let first = &notifications[0].context;
+    let context = AlarmContext {
+        device: first.device.clone(),
+        timestamp: first.timestamp,
+        incident: Some(format!("digest@{}", first.timestamp)),
+        ..AlarmContext::default()
+    };
+    let severity = notifications
+        .iter()
+        .map(|n| n.severity)
+        .max()
+        .unwrap_or(AlarmSeverity::Low);
+    let mut metrics: Vec<String> = Vec::new();
+    for notification in &notifications {
+        if !metrics.contains(&notification.metric) {
+            metrics.push(notification.metric.clone());
+        }
+    }
+    let description = notifications
+        .iter()
+        .map(|n| format!("{} [{}] {} = {}", n.message, n.severity, n.metric, n.value))
+        .collect::<Vec<String>>()
+        .join("\n");
+    Notification::new(
+        format!("{} alarms fired", notifications.len()),
+        severity,
+        description,
+        metrics.join(","),
+        notifications.len() as f64,
+    )
+    .context(context)
+}
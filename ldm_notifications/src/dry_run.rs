@@ -0,0 +1,38 @@
+use crate::core::config::{SendError, Sender};
+use chrono::{Local, TimeZone};
+use ldm_commons::Notification;
+
+/// Prints what would be sent, stamped with the time of the notification.
+#[derive(Debug)]
+pub struct DryRunSender {
+    name: String,
+}
+
+impl DryRunSender {
+    pub fn new(name: String) -> DryRunSender {
+        DryRunSender { name }
+    }
+}
+
+impl Sender for DryRunSender {
+    fn send(&self, notification: &Notification) -> Result<(), SendError> {
+        let timestamp = notification.context.timestamp;
+        let time = match Local.timestamp_opt(timestamp, 0).single() {
+            Some(time) => time.to_rfc3339(),
+            None => timestamp.to_string(),
+        };
+        println!(
+            "{}  {}  [{}] {}: {}",
+            time, self.name, notification.severity, notification.message, notification.description
+        );
+        Ok(())
+    }
+
+    fn acknowledge(&self, notification: &Notification, comment: &str) -> Result<(), SendError> {
+        println!(
+            "{}  acknowledged {}: {}",
+            self.name, notification.message, comment
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+use crate::core::config::{SendError, Sender};
+use ldm_commons::Notification;
+use serde::Deserialize;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use wait_timeout::ChildExt;
+
+#[derive(Deserialize, Debug)]
+pub struct ExecConfiguration {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Seconds to wait for the command before it is killed.
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+    /// Maximum number of commands running at the same time.
+    #[serde(default = "default_concurrency")]
+    pub concurrency: usize,
+}
+
+fn default_timeout() -> u64 {
+    30
+}
+
+fn default_concurrency() -> usize {
+    1
+}
+
+#[derive(Debug)]
+pub struct ExecSender {
+    command: String,
+    args: Vec<String>,
+    timeout: Duration,
+    concurrency: usize,
+    running: Arc<AtomicUsize>,
+}
+
+impl ExecSender {
+    pub fn new(conf: &ExecConfiguration) -> ExecSender {
+        ExecSender {
+            command: conf.command.clone(),
+            args: conf.args.clone(),
+            timeout: Duration::from_secs(conf.timeout),
+            concurrency: conf.concurrency,
+            running: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+}
+
+impl Sender for ExecSender {
+    fn send(&self, notification: &Notification) -> Result<(), SendError> {
+        if self.running.fetch_add(1, Ordering::SeqCst) >= self.concurrency {
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            return Err(SendError::Generic(format!(
+                "{} command(s) already running for '{}'",
+                self.concurrency, self.command
+            )));
+        }
+        let command = self.command.clone();
+        let args = self.args.clone();
+        let timeout = self.timeout;
+        let running = self.running.clone();
+        let notification = notification.clone();
+        std::thread::spawn(move || {
+            run(&command, &args, timeout, &notification);
+            running.fetch_sub(1, Ordering::SeqCst);
+        });
+        Ok(())
+    }
+}
+
+fn run(command: &str, args: &[String], timeout: Duration, notification: &Notification) {
+    let payload = match serde_json::to_vec(notification) {
+        Ok(payload) => payload,
+        Err(err) => {
+            error!(
+                "Error while serializing notification for '{}': {}",
+                command, err
+            );
+            return;
+        }
+    };
+    let mut child = match Command::new(command)
+        .args(args)
+        .env("LDM_MESSAGE", &notification.message)
+        .env("LDM_PRIORITY", notification.severity.as_str())
+        .env("LDM_DESCRIPTION", &notification.description)
+        .env("LDM_METRIC", &notification.metric)
+        .env("LDM_VALUE", notification.value.to_string())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(err) => {
+            error!("Error while starting '{}': {}", command, err);
+            return;
+        }
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        if let Err(err) = stdin.write_all(&payload) {
+            warn!("Error while writing notification to '{}': {}", command, err);
+        }
+    }
+    match child.wait_timeout(timeout) {
+        Ok(Some(status)) => match status.code() {
+            Some(0) => info!("Command '{}' completed successfully", command),
+            Some(code) => error!("Command '{}' exited with code {}", command, code),
+            None => error!("Command '{}' terminated by signal", command),
+        },
+        Ok(None) => {
+            error!(
+                "Command '{}' timed out after {:?}, killing",
+                command, timeout
+            );
+            if let Err(err) = child.kill() {
+                error!("Error while killing '{}': {}", command, err);
+            }
+            let _ = child.wait();
+        }
+        Err(err) => error!("Error while waiting '{}': {}", command, err),
+    }
+}
@@ -0,0 +1,58 @@
+use crate::core::config::{SendError, Sender};
+use crate::syslog::config::default_severities;
+use ldm_commons::journal::{JournalConfiguration, JournalWriter};
+use ldm_commons::syslog::Severity;
+use ldm_commons::{AlarmSeverity, Notification};
+use serde_derive::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Deserialize, Debug)]
+pub struct JournalSenderConfiguration {
+    #[serde(flatten)]
+    pub writer: JournalConfiguration,
+    /// Journal priority, as a syslog severity, per alarm severity.
+    #[serde(default = "default_severities")]
+    pub severities: HashMap<AlarmSeverity, Severity>,
+}
+
+#[derive(Debug)]
+pub struct JournalSender {
+    writer: JournalWriter,
+    severities: HashMap<AlarmSeverity, Severity>,
+}
+
+impl JournalSender {
+    pub fn new(writer: JournalWriter, conf: &JournalSenderConfiguration) -> JournalSender {
+        JournalSender {
+            writer,
+            severities: conf.severities.clone(),
+        }
+    }
+}
+
+impl Sender for JournalSender {
+    fn send(&self, notification: &Notification) -> Result<(), SendError> {
+        let severity = self
+            .severities
+            .get(&notification.severity)
+            .copied()
+            .unwrap_or(Severity::Warning);
+        let fields = [
+            ("ALARM_NAME", String::from(notification.alarm_name())),
+            ("SEVERITY", notification.severity.get_name()),
+            ("METRIC", notification.metric.clone()),
+            ("VALUE", notification.value.to_string()),
+        ];
+        let message = format!("{}: {}", notification.message, notification.description);
+        match self.writer.write(severity as u8, &message, &fields) {
+            Ok(_) => {
+                info!("Alarm written to journal");
+                Ok(())
+            }
+            Err(err) => Err(SendError::Generic(format!(
+                "Error occurred while writing alarm to journal {}",
+                err
+            ))),
+        }
+    }
+}
@@ -1,7 +1,16 @@
+pub mod alerts;
 pub mod core;
+pub mod digest;
+pub mod dry_run;
+pub mod errors;
+pub mod exec;
+pub mod journal;
+pub mod limiter;
 pub mod opsgenie;
+pub mod queue;
 pub mod sender;
 pub mod slack;
+pub mod syslog;
 
 #[macro_use]
 extern crate log;
@@ -0,0 +1,230 @@
+use crate::core::config::RateLimitConfiguration;
+use ldm_commons::{AlarmSeverity, Notification};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Instant;
+
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    /// Tokens added per second.
+    rate: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(conf: &RateLimitConfiguration) -> TokenBucket {
+        let capacity = conf.messages as f64;
+        TokenBucket {
+            capacity,
+            tokens: capacity,
+            rate: capacity / conf.period.max(1) as f64,
+            updated: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.updated = now;
+    }
+
+    fn available(&mut self) -> bool {
+        self.refill();
+        self.tokens >= 1.0
+    }
+
+    /// Seconds until a token is available.
+    fn wait(&mut self) -> u64 {
+        self.refill();
+        if self.tokens >= 1.0 || self.rate <= 0.0 {
+            0
+        } else {
+            ((1.0 - self.tokens) / self.rate).ceil() as u64
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Suppressed {
+    /// Highest severity among the dropped notifications.
+    severity: Option<AlarmSeverity>,
+    alarms: BTreeMap<String, u32>,
+}
+
+/// Per-sender and global token buckets, with a count of what was dropped while limited.
+#[derive(Debug)]
+pub struct RateLimiter {
+    global: Option<TokenBucket>,
+    senders: HashMap<String, TokenBucket>,
+    suppressed: HashMap<String, Suppressed>,
+}
+
+impl RateLimiter {
+    pub fn new(global: Option<&RateLimitConfiguration>) -> RateLimiter {
+        RateLimiter {
+            global: global.map(TokenBucket::new),
+            senders: HashMap::new(),
+            suppressed: HashMap::new(),
+        }
+    }
+
+    pub fn add_sender(&mut self, sender: &str, conf: &RateLimitConfiguration) {
+        self.senders
+            .insert(String::from(sender), TokenBucket::new(conf));
+    }
+
+    /// Takes a token from the sender and the global bucket, false if either is empty.
+    pub fn acquire(&mut self, sender: &str) -> bool {
+        let sender_ok = match self.senders.get_mut(sender) {
+            Some(bucket) => bucket.available(),
+            None => true,
+        };
+        let global_ok = match self.global.as_mut() {
+            Some(bucket) => bucket.available(),
+            None => true,
+        };
+        if !(sender_ok && global_ok) {
+            return false;
+        }
+        if let Some(bucket) = self.senders.get_mut(sender) {
+            bucket.tokens -= 1.0;
+        }
+        if let Some(bucket) = self.global.as_mut() {
+            bucket.tokens -= 1.0;
+        }
+        true
+    }
+
+    pub fn suppress(&mut self, sender: &str, notification: &Notification) {
+        let suppressed = self.suppressed.entry(String::from(sender)).or_default();
+        suppressed.severity = suppressed.severity.max(Some(notification.severity));
+        *suppressed
+            .alarms
+            .entry(notification.message.clone())
+            .or_insert(0) += 1;
+    }
+
+    /// Seconds until a pending summary can be sent.
+    pub fn next_due(&mut self) -> Option<u64> {
+        let senders: Vec<String> = self.suppressed.keys().cloned().collect();
+        senders
+            .iter()
+            .map(|sender| {
+                let sender_wait = self.senders.get_mut(sender).map_or(0, TokenBucket::wait);
+                let global_wait = self.global.as_mut().map_or(0, TokenBucket::wait);
+                sender_wait.max(global_wait)
+            })
+            .min()
+    }
+
+    /// Summaries of suppressed notifications for every sender that has a token again.
+    pub fn take_summaries(&mut self) -> Vec<(String, Notification)> {
+        let senders: Vec<String> = self.suppressed.keys().cloned().collect();
+        let mut summaries = Vec::new();
+        for sender in senders {
+            if !self.acquire(&sender) {
+                continue;
+            }
+            if let Some(suppressed) = self.suppressed.remove(&sender) {
+                summaries.push((sender, summarise(suppressed)));
+            }
+        }
+        summaries
+    }
+}
+
+fn summarise(suppressed: Suppressed) -> Notification {
+    let total: u32 = suppressed.alarms.values().sum();
+    let description = suppressed
+        .alarms
+        .iter()
+        .map(|(alarm, count)| format!("{} x{}", alarm, count))
+        .collect::<Vec<String>>()
+        .join("\n");
+    Notification::new(
+        format!("{} notification(s) dropped by rate limit", total),
+        suppressed.severity.unwrap_or(AlarmSeverity::Low),
+        description,
+        String::new(),
+        total as f64,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn limit(messages: u32, period: u64) -> RateLimitConfiguration {
+        serde_json::from_value(serde_json::json!({ "messages": messages, "period": period }))
+            .unwrap()
+    }
+
+    fn notification(message: &str, severity: AlarmSeverity) -> Notification {
+        Notification::new(
+            String::from(message),
+            severity,
+            String::new(),
+            String::from("cpu::usage"),
+            1.0,
+        )
+    }
+
+    #[test]
+    fn zero_messages_is_rejected() {
+        let result = serde_json::from_value::<RateLimitConfiguration>(
+            serde_json::json!({ "messages": 0, "period": 60 }),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn bucket_refills_over_the_period() {
+        let mut bucket = TokenBucket::new(&limit(2, 60));
+        assert!(bucket.available());
+        bucket.tokens = 0.0;
+        assert!(!bucket.available());
+        assert_eq!(bucket.wait(), 30);
+        bucket.updated -= Duration::from_secs(30);
+        assert!(bucket.available());
+        bucket.updated -= Duration::from_secs(600);
+        bucket.refill();
+        assert!((bucket.tokens - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn sender_and_global_limits_both_apply() {
+        let mut limiter = RateLimiter::new(Some(&limit(3, 60)));
+        limiter.add_sender("oncall", &limit(2, 60));
+        assert!(limiter.acquire("oncall"));
+        assert!(limiter.acquire("oncall"));
+        assert!(!limiter.acquire("oncall"));
+        assert!(limiter.acquire("slack"));
+        assert!(!limiter.acquire("slack"));
+    }
+
+    #[test]
+    fn suppressed_notifications_are_summarised_once_a_token_is_back() {
+        let mut limiter = RateLimiter::new(None);
+        limiter.add_sender("oncall", &limit(1, 60));
+        assert!(limiter.acquire("oncall"));
+        limiter.suppress("oncall", &notification("High CPU", AlarmSeverity::Warning));
+        limiter.suppress("oncall", &notification("High CPU", AlarmSeverity::Warning));
+        limiter.suppress("oncall", &notification("Disk full", AlarmSeverity::High));
+        assert_eq!(limiter.next_due(), Some(60));
+        assert!(limiter.take_summaries().is_empty());
+
+        limiter.senders.get_mut("oncall").unwrap().updated -= Duration::from_secs(60);
+        assert_eq!(limiter.next_due(), Some(0));
+        let summaries = limiter.take_summaries();
+        assert_eq!(summaries.len(), 1);
+        let (sender, summary) = &summaries[0];
+        assert_eq!(sender, "oncall");
+        assert_eq!(summary.message, "3 notification(s) dropped by rate limit");
+        assert_eq!(summary.severity, AlarmSeverity::High);
+        assert_eq!(summary.description, "Disk full x1\nHigh CPU x2");
+        assert_eq!(limiter.next_due(), None);
+    }
+}
@@ -1,44 +1,153 @@
-use crate::core::config::Sender;
-use ldm_commons::Notification;
-use opsgenie_rs::alert::models::{AlertData, Priority};
+use crate::core::config::{SendError, Sender};
+use ldm_commons::{AlarmSeverity, Notification};
+use opsgenie_rs::alert::models::{ActionData, AlertData, Identifier, Priority, Responder};
 use opsgenie_rs::alert::operations::Alert;
+use opsgenie_rs::client::{ClientConfiguration, OpsgenieClient};
 use serde::Deserialize;
+use std::collections::HashMap;
+use tokio::runtime::Handle;
 
 #[derive(Deserialize, Debug)]
 pub struct OpsgenieConfiguration {
     pub key: String,
+    /// Opsgenie priority per alarm severity.
+    #[serde(default = "default_priorities")]
+    pub priorities: HashMap<AlarmSeverity, Priority>,
+    #[serde(default = "default_tags")]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub responders: Vec<Responder>,
+    /// Region, base url, timeouts and proxy of the API client.
+    #[serde(flatten)]
+    pub client: ClientConfiguration,
+}
+
+fn default_priorities() -> HashMap<AlarmSeverity, Priority> {
+    let mut priorities = HashMap::new();
+    priorities.insert(AlarmSeverity::Critical, Priority::P1);
+    priorities.insert(AlarmSeverity::High, Priority::P2);
+    priorities.insert(AlarmSeverity::Warning, Priority::P3);
+    priorities.insert(AlarmSeverity::Low, Priority::P4);
+    priorities.insert(AlarmSeverity::Info, Priority::P5);
+    priorities
+}
+
+fn default_tags() -> Vec<String> {
+    vec![String::from("ldm")]
 }
 
 #[derive(Debug)]
 pub struct OpsGenieSender {
-    pub key: String,
+    client: OpsgenieClient,
+    runtime: Handle,
+    priorities: HashMap<AlarmSeverity, Priority>,
+    tags: Vec<String>,
+    responders: Vec<Responder>,
+}
+
+impl OpsGenieSender {
+    /// Requests are run on `runtime` while the calling thread blocks for the result.
+    pub fn new(conf: &OpsgenieConfiguration, runtime: Handle) -> Result<OpsGenieSender, SendError> {
+        let client = conf.client.client(&conf.key).map_err(|err| {
+            SendError::Generic(format!(
+                "Error occurred while building opsgenie client {}",
+                err
+            ))
+        })?;
+        Ok(OpsGenieSender {
+            client,
+            runtime,
+            priorities: conf.priorities.clone(),
+            tags: conf.tags.clone(),
+            responders: conf.responders.clone(),
+        })
+    }
+}
+
+fn details(notification: &Notification) -> HashMap<String, String> {
+    let context = &notification.context;
+    let mut details = HashMap::new();
+    details.insert(String::from("metric"), notification.metric.clone());
+    details.insert(String::from("value"), notification.value.to_string());
+    if !context.device.name.is_empty() {
+        details.insert(String::from("device"), context.device.name.clone());
+        details.insert(String::from("ip"), context.device.ip.clone());
+    }
+    if let Some(dimension) = &context.dimension {
+        details.insert(String::from("dimension"), dimension.clone());
+    }
+    if let Some(threshold) = context.threshold {
+        details.insert(String::from("threshold"), threshold.to_string());
+    }
+    if let Some(computed) = context.computed {
+        details.insert(String::from("computed"), computed.to_string());
+    }
+    if let Some(method) = &context.method {
+        details.insert(String::from("method"), method.clone());
+    }
+    details
+}
+
+/// Incident of the alarm, so every notification of an incident updates the same alert.
+fn alias(notification: &Notification) -> String {
+    match &notification.context.incident {
+        Some(incident) => incident.clone(),
+        None => String::from(notification.alarm_name()),
+    }
 }
 
 impl Sender for OpsGenieSender {
-    fn send(&self, notification: &Notification) {
-        let data = AlertData {
-            message: notification.message.clone(),
-            alias: Some(notification.message.clone()),
-            description: Some(notification.description.clone()),
-            responders: None,
-            visible_to: None,
-            actions: None,
-            tags: Some(vec![String::from("ldm")]),
-            details: None,
-            entity: None,
-            source: None,
-            priority: Some(if notification.priority == "high" {
-                Priority::P2
-            } else {
-                Priority::P4
-            }),
-            user: None,
-            note: None,
+    fn send(&self, notification: &Notification) -> Result<(), SendError> {
+        let device = &notification.context.device;
+        let mut data = AlertData::new(notification.message.clone())
+            .alias(alias(notification))
+            .description(notification.description.clone())
+            .tags(self.tags.clone())
+            .details(details(notification));
+        if let Some(priority) = self.priorities.get(&notification.severity) {
+            data = data.priority(priority.clone());
+        }
+        if !self.responders.is_empty() {
+            data = data.responders(self.responders.clone());
+        }
+        if !device.name.is_empty() {
+            data = data.entity(device.name.clone());
+        }
+        if !device.ip.is_empty() {
+            data = data.source(device.ip.clone());
+        }
+        let result = self.runtime.block_on(Alert::create(&self.client, data));
+        match result {
+            Ok(_) => {
+                info!("Alert created successfully");
+                Ok(())
+            }
+            Err(err) => Err(SendError::Generic(format!(
+                "Error occurred while creating alert {}",
+                err
+            ))),
+        }
+    }
+
+    fn acknowledge(&self, notification: &Notification, comment: &str) -> Result<(), SendError> {
+        let data = ActionData {
+            source: Some(String::from("ldm")),
+            note: Some(String::from(comment)).filter(|note| !note.is_empty()),
+            ..ActionData::default()
         };
-        let result = Alert::create(&self.key, data);
+        let identifier = Identifier::Alias(alias(notification));
+        let result = self
+            .runtime
+            .block_on(Alert::acknowledge(&self.client, &identifier, data));
         match result {
-            Ok(_) => info!("Alert created successfully"),
-            Err(err) => error!("Error occurred while creating alert {}", err),
+            Ok(_) => {
+                info!("Alert {} acknowledged", identifier.value());
+                Ok(())
+            }
+            Err(err) => Err(SendError::Generic(format!(
+                "Error occurred while acknowledging alert {}",
+                err
+            ))),
         }
     }
 }
@@ -0,0 +1,208 @@
+use crate::core::config::RetryConfiguration;
+use ldm_commons::Notification;
+use rand::Rng;
+use serde_derive::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Delivery {
+    pub sender: String,
+    pub notification: Notification,
+    pub attempts: u32,
+    pub created_at: u64,
+    pub next_attempt: u64,
+}
+
+impl Delivery {
+    pub fn new(sender: String, notification: Notification) -> Delivery {
+        let now = now();
+        Delivery {
+            sender,
+            notification,
+            attempts: 0,
+            created_at: now,
+            next_attempt: now,
+        }
+    }
+
+    /// Schedules the next attempt, returns false when the retry budget is exhausted.
+    pub fn reschedule(&mut self, retry: &RetryConfiguration) -> bool {
+        self.attempts += 1;
+        let now = now();
+        if self.attempts > retry.attempts || now.saturating_sub(self.created_at) >= retry.max_age {
+            return false;
+        }
+        let delay = retry
+            .backoff
+            .saturating_mul(1 << (self.attempts - 1).min(31))
+            .min(retry.max_backoff);
+        let jitter = if delay > 1 {
+            rand::thread_rng().gen_range(0, delay / 2 + 1)
+        } else {
+            0
+        };
+        self.next_attempt = now + delay / 2 + jitter;
+        true
+    }
+}
+
+/// Notifications waiting for a retry, mirrored to a file after every change.
+#[derive(Debug)]
+pub struct DeliveryQueue {
+    path: Option<PathBuf>,
+    pending: Vec<Delivery>,
+}
+
+impl DeliveryQueue {
+    pub fn load(path: Option<PathBuf>) -> DeliveryQueue {
+        let pending = match &path {
+            Some(path) if path.exists() => match File::open(path) {
+                Ok(file) => match serde_json::from_reader(BufReader::new(file)) {
+                    Ok(pending) => pending,
+                    Err(err) => {
+                        error!("Error while reading delivery queue {:?}: {}", path, err);
+                        Vec::new()
+                    }
+                },
+                Err(err) => {
+                    error!("Error while opening delivery queue {:?}: {}", path, err);
+                    Vec::new()
+                }
+            },
+            _ => Vec::new(),
+        };
+        if !pending.is_empty() {
+            info!("{} pending notification(s) restored", pending.len());
+        }
+        DeliveryQueue { path, pending }
+    }
+
+    pub fn push(&mut self, delivery: Delivery) {
+        self.pending.push(delivery);
+        self.persist();
+    }
+
+    pub fn take_due(&mut self) -> Vec<Delivery> {
+        let now = now();
+        let (due, pending) = self
+            .pending
+            .drain(..)
+            .partition(|delivery| delivery.next_attempt <= now);
+        self.pending = pending;
+        if !due.is_empty() {
+            self.persist();
+        }
+        due
+    }
+
+    /// Seconds until the next delivery is due.
+    pub fn next_due(&self) -> Option<u64> {
+        let now = now();
+        self.pending
+            .iter()
+            .map(|delivery| delivery.next_attempt.saturating_sub(now))
+            .min()
+    }
+
+    pub fn persist(&self) {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+        let result = File::create(path)
+            .map_err(|err| err.to_string())
+            .and_then(|file| {
+                serde_json::to_writer(BufWriter::new(file), &self.pending)
+                    .map_err(|err| err.to_string())
+            });
+        if let Err(err) = result {
+            error!("Error while writing delivery queue {:?}: {}", path, err);
+        }
+    }
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ldm_commons::AlarmSeverity;
+
+    fn delivery(sender: &str) -> Delivery {
+        Delivery::new(
+            String::from(sender),
+            Notification::new(
+                String::from("High CPU"),
+                AlarmSeverity::High,
+                String::from("Alarm data set -> 0.9"),
+                String::from("cpu::usage"),
+                0.9,
+            ),
+        )
+    }
+
+    fn retry(attempts: u32, max_age: u64) -> RetryConfiguration {
+        RetryConfiguration {
+            attempts,
+            backoff: 10,
+            max_backoff: 60,
+            max_age,
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_with_jitter_up_to_the_maximum() {
+        let retry = retry(10, 3600);
+        let mut delivery = delivery("oncall");
+        for delay in [10, 20, 40, 60, 60] {
+            let before = now();
+            assert!(delivery.reschedule(&retry));
+            let wait = delivery.next_attempt - before;
+            // Half the delay plus a jitter of up to the other half, a second may pass meanwhile.
+            assert!(
+                wait >= delay / 2 && wait <= delay + 1,
+                "{} of {}",
+                wait,
+                delay
+            );
+        }
+    }
+
+    #[test]
+    fn gives_up_after_the_attempts_or_max_age() {
+        let mut delivery = delivery("oncall");
+        assert!(delivery.reschedule(&retry(2, 3600)));
+        assert!(delivery.reschedule(&retry(2, 3600)));
+        assert!(!delivery.reschedule(&retry(2, 3600)));
+
+        let mut delivery = self::delivery("oncall");
+        delivery.created_at -= 600;
+        assert!(!delivery.reschedule(&retry(10, 600)));
+    }
+
+    #[test]
+    fn pending_deliveries_are_persisted_and_loaded() {
+        let path = std::env::temp_dir().join(format!("ldm_queue_{}.json", std::process::id()));
+        let mut queue = DeliveryQueue::load(Some(path.clone()));
+        let mut later = delivery("oncall");
+        later.next_attempt = now() + 600;
+        queue.push(delivery("syslog"));
+        queue.push(later);
+
+        let mut loaded = DeliveryQueue::load(Some(path.clone()));
+        let due = loaded.take_due();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].sender, "syslog");
+        assert!(loaded.next_due().unwrap() > 500);
+        assert_eq!(DeliveryQueue::load(Some(path.clone())).pending.len(), 1);
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -1,61 +1,386 @@
-use crate::core::config::{NotificationConfiguration, Sender};
+use crate::alerts::OpenAlerts;
+use crate::core::config::{
+    DeliveryConfiguration, NotificationConfiguration, RetryConfiguration, SendError, Sender,
+    SenderConfiguration,
+};
+use crate::digest::DigestBuffer;
+use crate::dry_run::DryRunSender;
+use crate::errors::Error;
+use crate::exec::config::ExecSender;
+use crate::journal::config::JournalSender;
+use crate::limiter::RateLimiter;
 use crate::opsgenie::config::OpsGenieSender;
-use ldm_commons::AlarmSenderCommands;
-use std::sync::mpsc::Receiver;
+use crate::queue::{now, Delivery, DeliveryQueue};
+use crate::syslog::config::SyslogSender;
+use ldm_commons::history::{EventKind, HistoryCommands, HistoryEvent};
+use ldm_commons::journal::JournalWriter;
+use ldm_commons::syslog::SyslogWriter;
+use ldm_commons::template::TemplateConfiguration;
+use ldm_commons::{AlarmSenderCommands, AlarmSeverity, Notification};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender as ChannelSender};
+use std::time::Duration;
+use tokio::runtime::Handle;
+
+/// Upper bound for waiting on the channel, so retries are checked periodically.
+const IDLE_WAIT: u64 = 60;
+
+#[derive(Debug)]
+struct Target {
+    name: String,
+    sender: Box<dyn Sender>,
+    retry: RetryConfiguration,
+    template: Option<TemplateConfiguration>,
+}
 
 #[derive(Debug)]
 pub struct AlarmSender {
     notification_channel: Receiver<AlarmSenderCommands>,
     configurations: Vec<NotificationConfiguration>,
+    routes: HashMap<AlarmSeverity, Vec<String>>,
+    digest: Option<DigestBuffer>,
+    alerts: OpenAlerts,
+    limiter: RateLimiter,
+    queue: DeliveryQueue,
+    runtime: Handle,
+    history: Option<ChannelSender<HistoryCommands>>,
+    dry_run: bool,
 }
 
 impl AlarmSender {
     pub fn new(
         notification_channel: Receiver<AlarmSenderCommands>,
         configurations: Vec<NotificationConfiguration>,
-    ) -> AlarmSender {
-        AlarmSender {
+        delivery: DeliveryConfiguration,
+        runtime: Handle,
+    ) -> Result<AlarmSender, Error> {
+        if delivery.queue.is_some() {
+            check_ids(&configurations)?;
+        }
+        Ok(AlarmSender {
             notification_channel,
             configurations,
-        }
+            routes: delivery.routes,
+            digest: delivery.digest.as_ref().map(DigestBuffer::new),
+            alerts: OpenAlerts::default(),
+            limiter: RateLimiter::new(delivery.rate_limit.as_ref()),
+            queue: DeliveryQueue::load(delivery.queue.map(PathBuf::from)),
+            runtime,
+            history: None,
+            dry_run: false,
+        })
+    }
+
+    /// Delivery results are sent to `history`.
+    pub fn history(mut self, history: ChannelSender<HistoryCommands>) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Prints what every configured sender would get instead of sending it. Digests and
+    /// rate limits are skipped, they follow the wall clock rather than the notifications.
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self.digest = None;
+        self.limiter = RateLimiter::new(None);
+        self
     }
 
     pub fn start(&mut self) {
-        let senders = self.setup_senders();
+        let targets = self.setup_senders();
         loop {
-            match self.notification_channel.recv() {
+            let wait = self
+                .queue
+                .next_due()
+                .into_iter()
+                .chain(self.digest.as_ref().and_then(DigestBuffer::next_due))
+                .chain(self.limiter.next_due())
+                .min()
+                .unwrap_or(IDLE_WAIT)
+                .min(IDLE_WAIT);
+            match self
+                .notification_channel
+                .recv_timeout(Duration::from_secs(wait))
+            {
                 Ok(command) => match command {
                     AlarmSenderCommands::Stop => {
                         info!("Stopping Sender");
+                        self.flush_digest(&targets, true);
+                        self.queue.persist();
                         break;
                     }
                     AlarmSenderCommands::Send(notification) => {
                         info!("Send Message received");
-                        for sender in &senders {
-                            sender.send(&notification);
+                        for target in self.route(&notification, &targets) {
+                            let notification = match &target.template {
+                                Some(template) => template.apply(notification.clone()),
+                                None => notification.clone(),
+                            };
+                            self.dispatch(target, notification);
                         }
                     }
+                    AlarmSenderCommands::Acknowledge {
+                        notification,
+                        comment,
+                    } => self.acknowledge(notification, &comment, &targets),
                 },
+                Err(RecvTimeoutError::Timeout) => {}
                 Err(err) => {
                     error!("{}", err);
+                    self.queue.persist();
                     break;
                 }
             }
+            self.flush_digest(&targets, false);
+            self.send_summaries(&targets);
+            self.retry_due(&targets);
+        }
+    }
+
+    fn dispatch(&mut self, target: &Target, notification: Notification) {
+        match &mut self.digest {
+            Some(digest) if !digest.bypasses(&notification) => {
+                self.alerts.digested(&target.name, &notification);
+                digest.add(&target.name, notification)
+            }
+            _ => self.send_limited(target, notification),
+        }
+    }
+
+    fn send_limited(&mut self, target: &Target, notification: Notification) {
+        if self.limiter.acquire(&target.name) {
+            self.deliver(target, Delivery::new(target.name.clone(), notification));
+        } else {
+            warn!(
+                "Rate limit reached for {}, dropping '{}'",
+                target.name, notification.message
+            );
+            self.limiter.suppress(&target.name, &notification);
+        }
+    }
+
+    fn send_summaries(&mut self, targets: &[Target]) {
+        for (name, notification) in self.limiter.take_summaries() {
+            if let Some(target) = targets.iter().find(|target| target.name == name) {
+                self.deliver(target, Delivery::new(name, notification));
+            }
         }
     }
 
-    fn setup_senders(&mut self) -> Vec<Box<dyn Sender>> {
-        let mut senders: Vec<Box<dyn Sender>> = Vec::new();
-        for conf in &self.configurations {
-            match conf {
-                NotificationConfiguration::OpsGenie(conf) => {
-                    senders.push(Box::new(OpsGenieSender {
-                        key: String::from(&conf.key),
-                    }))
+    fn flush_digest(&mut self, targets: &[Target], all: bool) {
+        let batches = match &mut self.digest {
+            Some(digest) if all => digest.take_all(),
+            Some(digest) => digest.take_due(),
+            None => return,
+        };
+        for (name, notification) in batches {
+            self.alerts.flushed(&name, &notification);
+            if let Some(target) = targets.iter().find(|target| target.name == name) {
+                self.send_limited(target, notification);
+            }
+        }
+    }
+
+    /// Acknowledges the incident on every sender that got it, the routes of the alarm cover
+    /// what was sent before a restart.
+    fn acknowledge(&mut self, notification: Notification, comment: &str, targets: &[Target]) {
+        let mut alerts = self.alerts.acknowledge(&notification);
+        for target in self.route(&notification, targets) {
+            if !alerts.iter().any(|(name, sent)| {
+                name == &target.name && sent.context.incident == notification.context.incident
+            }) {
+                alerts.push((target.name.clone(), notification.clone()));
+            }
+        }
+        for (name, alert) in alerts {
+            if let Some(target) = targets.iter().find(|target| target.name == name) {
+                if let Err(err) = target.sender.acknowledge(&alert, comment) {
+                    error!(
+                        "Error while acknowledging '{}' on {}: {}",
+                        alert.message, target.name, err
+                    );
                 }
-                NotificationConfiguration::Slack(_) => {}
             }
         }
-        senders
+    }
+
+    /// Alarm `notify` lists win over severity routes, without either the notification goes to every sender.
+    fn route<'a>(&self, notification: &Notification, targets: &'a [Target]) -> Vec<&'a Target> {
+        let ids = if !notification.routes.is_empty() {
+            &notification.routes
+        } else {
+            match self.routes.get(&notification.severity) {
+                Some(ids) => ids,
+                None => return targets.iter().collect(),
+            }
+        };
+        let mut routed = Vec::new();
+        for id in ids {
+            match targets.iter().find(|target| &target.name == id) {
+                Some(target) => routed.push(target),
+                None => warn!(
+                    "Sender {} for '{}' is not configured",
+                    id, notification.message
+                ),
+            }
+        }
+        routed
+    }
+
+    fn retry_due(&mut self, targets: &[Target]) {
+        for delivery in self.queue.take_due() {
+            match targets.iter().find(|target| target.name == delivery.sender) {
+                Some(target) => self.deliver(target, delivery),
+                None => warn!(
+                    "Dropping notification '{}', sender {} is not configured",
+                    delivery.notification.message, delivery.sender
+                ),
+            }
+        }
+    }
+
+    fn deliver(&mut self, target: &Target, mut delivery: Delivery) {
+        let result = target.sender.send(&delivery.notification);
+        self.record(target, &delivery, &result);
+        if result.is_ok() {
+            self.alerts.sent(&target.name, &delivery.notification);
+        }
+        if let Err(err) = result {
+            if delivery.reschedule(&target.retry) {
+                warn!(
+                    "Delivery to {} failed (attempt {}), retrying: {}",
+                    target.name, delivery.attempts, err
+                );
+                self.queue.push(delivery);
+            } else {
+                error!(
+                    "Giving up delivery of '{}' to {} after {} attempt(s): {}",
+                    delivery.notification.message, target.name, delivery.attempts, err
+                );
+            }
+        }
+    }
+
+    fn record(&self, target: &Target, delivery: &Delivery, result: &Result<(), SendError>) {
+        let history = match &self.history {
+            Some(history) => history,
+            None => return,
+        };
+        let notification = &delivery.notification;
+        let event = HistoryEvent {
+            timestamp: now() as i64,
+            alarm: String::from(notification.alarm_name()),
+            metric: notification.metric.clone(),
+            dimension: notification.context.dimension.clone(),
+            value: notification.value,
+            kind: EventKind::Delivery {
+                sender: target.name.clone(),
+                delivered: result.is_ok(),
+                attempt: delivery.attempts + 1,
+                error: result.as_ref().err().map(ToString::to_string),
+            },
+        };
+        if let Err(err) = history.send(HistoryCommands::Record(event)) {
+            error!("Error while sending to history channel {}", err);
+        }
+    }
+
+    fn setup_senders(&mut self) -> Vec<Target> {
+        let mut targets: Vec<Target> = Vec::new();
+        for (index, conf) in self.configurations.iter().enumerate() {
+            let name = match &conf.id {
+                Some(id) => id.clone(),
+                None => format!("{}#{}", conf.sender.kind(), index),
+            };
+            let sender: Box<dyn Sender> = match &conf.sender {
+                SenderConfiguration::Slack(_) => continue,
+                _ if self.dry_run => Box::new(DryRunSender::new(name.clone())),
+                SenderConfiguration::OpsGenie(conf) => {
+                    match OpsGenieSender::new(conf, self.runtime.clone()) {
+                        Ok(sender) => Box::new(sender),
+                        Err(err) => {
+                            error!("{}", err);
+                            continue;
+                        }
+                    }
+                }
+                SenderConfiguration::Exec(conf) => Box::new(ExecSender::new(conf)),
+                SenderConfiguration::Syslog(conf) => match SyslogWriter::new(&conf.writer) {
+                    Ok(writer) => Box::new(SyslogSender::new(writer, conf)),
+                    Err(err) => {
+                        error!("Error occurred while opening syslog socket {}", err);
+                        continue;
+                    }
+                },
+                SenderConfiguration::Journal(conf) => match JournalWriter::new(&conf.writer) {
+                    Ok(writer) => Box::new(JournalSender::new(writer, conf)),
+                    Err(err) => {
+                        error!("Error occurred while opening journal socket {}", err);
+                        continue;
+                    }
+                },
+            };
+            if let (Some(rate_limit), false) = (&conf.rate_limit, self.dry_run) {
+                self.limiter.add_sender(&name, rate_limit);
+            }
+            targets.push(Target {
+                name,
+                sender,
+                retry: conf.retry.clone(),
+                template: conf.template.clone(),
+            });
+        }
+        targets
+    }
+}
+
+/// Queued retries are keyed by sender id, a default `<kind>#<index>` would send them to
+/// another sender once the senders are reordered.
+fn check_ids(configurations: &[NotificationConfiguration]) -> Result<(), Error> {
+    let mut ids = Vec::new();
+    for (index, conf) in configurations.iter().enumerate() {
+        match &conf.id {
+            Some(id) if ids.contains(&id) => {
+                return Err(Error::Generic(format!("Sender id {} is used twice", id)));
+            }
+            Some(id) => ids.push(id),
+            None => {
+                return Err(Error::Generic(format!(
+                    "Sender {}#{} needs an id while the delivery queue is enabled",
+                    conf.sender.kind(),
+                    index
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configurations(ids: &[Option<&str>]) -> Vec<NotificationConfiguration> {
+        ids.iter()
+            .map(|id| {
+                serde_json::from_value(serde_json::json!({
+                    "id": id,
+                    "kind": "journal",
+                }))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn queued_senders_need_unique_ids() {
+        assert!(check_ids(&configurations(&[Some("oncall"), Some("journal")])).is_ok());
+        let err = check_ids(&configurations(&[Some("oncall"), None])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Sender journal#1 needs an id while the delivery queue is enabled"
+        );
+        assert!(check_ids(&configurations(&[Some("oncall"), Some("oncall")])).is_err());
     }
 }
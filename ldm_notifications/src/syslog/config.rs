@@ -0,0 +1,66 @@
+use crate::core::config::{SendError, Sender};
+use ldm_commons::syslog::{Severity, SyslogConfiguration, SyslogWriter};
+use ldm_commons::{AlarmSeverity, Notification};
+use serde_derive::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Deserialize, Debug)]
+pub struct SyslogSenderConfiguration {
+    #[serde(flatten)]
+    pub writer: SyslogConfiguration,
+    /// Syslog severity per alarm severity.
+    #[serde(default = "default_severities")]
+    pub severities: HashMap<AlarmSeverity, Severity>,
+}
+
+pub(crate) fn default_severities() -> HashMap<AlarmSeverity, Severity> {
+    let mut severities = HashMap::new();
+    severities.insert(AlarmSeverity::Critical, Severity::Critical);
+    severities.insert(AlarmSeverity::High, Severity::Error);
+    severities.insert(AlarmSeverity::Warning, Severity::Warning);
+    severities.insert(AlarmSeverity::Low, Severity::Notice);
+    severities.insert(AlarmSeverity::Info, Severity::Info);
+    severities
+}
+
+#[derive(Debug)]
+pub struct SyslogSender {
+    writer: SyslogWriter,
+    severities: HashMap<AlarmSeverity, Severity>,
+}
+
+impl SyslogSender {
+    pub fn new(writer: SyslogWriter, conf: &SyslogSenderConfiguration) -> SyslogSender {
+        SyslogSender {
+            writer,
+            severities: conf.severities.clone(),
+        }
+    }
+}
+
+impl Sender for SyslogSender {
+    fn send(&self, notification: &Notification) -> Result<(), SendError> {
+        let severity = self
+            .severities
+            .get(&notification.severity)
+            .copied()
+            .unwrap_or(Severity::Warning);
+        let params = [
+            ("alarm", String::from(notification.alarm_name())),
+            ("severity", notification.severity.get_name()),
+            ("metric", notification.metric.clone()),
+            ("value", notification.value.to_string()),
+        ];
+        let message = format!("{}: {}", notification.message, notification.description);
+        match self.writer.write(severity, "alarm", &params, &message) {
+            Ok(_) => {
+                info!("Alarm written to syslog");
+                Ok(())
+            }
+            Err(err) => Err(SendError::Generic(format!(
+                "Error occurred while writing alarm to syslog {}",
+                err
+            ))),
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use crate::history::{AlarmHistory, HistoryQuery};
+use ldm_metrics::ack::{AckRequest, Acknowledgements};
+use ldm_metrics::silence::{SilenceConfiguration, Silences};
+use serde::Serialize;
+use serde_derive::Deserialize;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+#[derive(Deserialize, Debug)]
+pub struct ApiConfiguration {
+    /// Address the api listens on, keep it on localhost since there is no authentication.
+    #[serde(default = "default_address")]
+    pub address: String,
+}
+
+fn default_address() -> String {
+    String::from("127.0.0.1:9292")
+}
+
+type ApiError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Local HTTP api to manage the service at runtime.
+///
+/// * `GET /silences` lists the silences
+/// * `POST /silences` adds a silence, the body is a json silence definition
+/// * `DELETE /silences/{id}` removes a silence
+/// * `GET /history?alarm=..&metric=..&since=..&until=..&limit=..` lists alarm transitions and deliveries
+/// * `GET /alarms` lists the firing alarms and their acknowledgement
+/// * `POST /alarms/ack` acknowledges firing alarms, the body is a json acknowledge request
+pub struct ApiServer {
+    server: Server,
+    silences: Silences,
+    history: AlarmHistory,
+    acknowledgements: Acknowledgements,
+}
+
+impl ApiServer {
+    pub fn new(
+        configuration: &ApiConfiguration,
+        silences: Silences,
+        history: AlarmHistory,
+        acknowledgements: Acknowledgements,
+    ) -> Result<ApiServer, ApiError> {
+        Ok(ApiServer {
+            server: Server::http(configuration.address.as_str())?,
+            silences,
+            history,
+            acknowledgements,
+        })
+    }
+
+    pub fn start(&self) {
+        for request in self.server.incoming_requests() {
+            self.handle(request);
+        }
+    }
+
+    fn handle(&self, mut request: Request) {
+        let url = String::from(request.url());
+        let mut url = url.splitn(2, '?');
+        let path: Vec<String> = url
+            .next()
+            .unwrap_or("")
+            .trim_matches('/')
+            .split('/')
+            .map(String::from)
+            .collect();
+        let path: Vec<&str> = path.iter().map(String::as_str).collect();
+        let query = url.next().unwrap_or("");
+        let (status, body) = match (request.method(), path.as_slice()) {
+            (Method::Get, ["silences"]) => json(200, &self.silences.list()),
+            (Method::Post, ["silences"]) => match read_json::<SilenceConfiguration>(&mut request) {
+                Ok(silence) => match self.silences.add(silence) {
+                    Ok(silence) => {
+                        info!("Silence {:?} added", silence.silence.id);
+                        json(201, &silence)
+                    }
+                    Err(err) => message(400, &err.to_string()),
+                },
+                Err(err) => message(400, &err),
+            },
+            (Method::Delete, ["silences", id]) => {
+                if self.silences.remove(id) {
+                    info!("Silence {} removed", id);
+                    message(200, "Silence removed")
+                } else {
+                    message(404, "Silence not found")
+                }
+            }
+            (Method::Get, ["history"]) => {
+                let pairs = url::form_urlencoded::parse(query.as_bytes()).into_owned();
+                match HistoryQuery::parse(pairs) {
+                    Ok(query) => json(200, &self.history.query(&query)),
+                    Err(err) => message(400, &err),
+                }
+            }
+            (Method::Get, ["alarms"]) => json(200, &self.acknowledgements.firing()),
+            (Method::Post, ["alarms", "ack"]) => match read_json::<AckRequest>(&mut request) {
+                Ok(ack) => match self.acknowledgements.acknowledge(ack) {
+                    Ok(acknowledged) if acknowledged.is_empty() => {
+                        message(404, "No firing alarm matches")
+                    }
+                    Ok(acknowledged) => json(200, &acknowledged),
+                    Err(err) => message(400, &err.to_string()),
+                },
+                Err(err) => message(400, &err),
+            },
+            _ => message(404, "Not found"),
+        };
+        let header = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+        let response = Response::from_string(body)
+            .with_status_code(status)
+            .with_header(header);
+        if let Err(err) = request.respond(response) {
+            error!("Error occurred while responding api request {}", err);
+        }
+    }
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(request: &mut Request) -> Result<T, String> {
+    let mut body = String::new();
+    if let Err(err) = request.as_reader().read_to_string(&mut body) {
+        return Err(format!("Error occurred while reading request {}", err));
+    }
+    serde_json::from_str(&body).map_err(|err| format!("Invalid request body {}", err))
+}
+
+fn json<T: Serialize>(status: u16, body: &T) -> (u16, String) {
+    match serde_json::to_string(body) {
+        Ok(body) => (status, body),
+        Err(err) => message(500, &err.to_string()),
+    }
+}
+
+fn message(status: u16, message: &str) -> (u16, String) {
+    (
+        status,
+        serde_json::json!({ "message": message }).to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::HistoryConfiguration;
+    use ldm_metrics::inhibit::Inhibitor;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::sync::mpsc::channel;
+
+    /// Starts an api on a free port, returns its address.
+    fn start(silences: Silences) -> String {
+        let (notification_tx, _) = channel();
+        let server = ApiServer::new(
+            &ApiConfiguration {
+                address: String::from("127.0.0.1:0"),
+            },
+            silences,
+            AlarmHistory::new(&HistoryConfiguration::default()),
+            Acknowledgements::new(Inhibitor::default(), notification_tx),
+        )
+        .unwrap();
+        let address = server.server.server_addr().to_string();
+        std::thread::spawn(move || server.start());
+        address
+    }
+
+    fn call(address: &str, method: &str, path: &str, body: &str) -> (u16, serde_json::Value) {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            method,
+            path,
+            address,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[test]
+    fn silences_are_managed_over_http() {
+        let silences = Silences::default();
+        let address = start(silences.clone());
+
+        let (status, added) = call(
+            &address,
+            "POST",
+            "/silences",
+            r#"{"id": "deploy", "alarm": "High CPU", "duration": 1800}"#,
+        );
+        assert_eq!(status, 201);
+        assert_eq!(added["id"], "deploy");
+        assert_eq!(added["active"], true);
+        assert_eq!(silences.list().len(), 1);
+
+        let (status, listed) = call(&address, "GET", "/silences", "");
+        assert_eq!(status, 200);
+        assert_eq!(listed[0]["alarm"], "High CPU");
+
+        let (status, _) = call(&address, "POST", "/silences", r#"{"id": "deploy"}"#);
+        assert_eq!(status, 400);
+        let (status, _) = call(&address, "POST", "/silences", "not json");
+        assert_eq!(status, 400);
+
+        let (status, _) = call(&address, "DELETE", "/silences/deploy", "");
+        assert_eq!(status, 200);
+        let (status, _) = call(&address, "DELETE", "/silences/deploy", "");
+        assert_eq!(status, 404);
+        assert!(silences.list().is_empty());
+    }
+}
@@ -0,0 +1,98 @@
+use ldm_metrics::collector::CollectorHealth;
+use opsgenie_rs::client::{ClientConfiguration, OpsgenieClient};
+use opsgenie_rs::errors::OpsgenieError;
+use opsgenie_rs::heartbeat::operations::Heartbeat;
+use serde_derive::Deserialize;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::Duration;
+use tokio::runtime::Handle;
+
+#[derive(Deserialize, Debug)]
+pub struct HeartbeatConfiguration {
+    pub key: String,
+    /// Name of the heartbeat defined in Opsgenie.
+    pub name: String,
+    /// Seconds between pings.
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+    /// Pings stop when no metric was collected within this many seconds.
+    #[serde(default = "default_healthy_within")]
+    pub healthy_within: i64,
+    #[serde(flatten)]
+    pub client: ClientConfiguration,
+}
+
+fn default_interval() -> u64 {
+    60
+}
+
+fn default_healthy_within() -> i64 {
+    300
+}
+
+#[derive(Debug)]
+pub enum HeartbeatCommands {
+    Stop,
+}
+
+/// Pings an Opsgenie heartbeat while the collector keeps polling metrics.
+#[derive(Debug)]
+pub struct HeartbeatPinger {
+    configuration: HeartbeatConfiguration,
+    health: CollectorHealth,
+    client: OpsgenieClient,
+    runtime: Handle,
+}
+
+impl HeartbeatPinger {
+    pub fn new(
+        configuration: HeartbeatConfiguration,
+        health: CollectorHealth,
+        runtime: Handle,
+    ) -> Result<HeartbeatPinger, OpsgenieError> {
+        let client = configuration.client.client(&configuration.key)?;
+        Ok(HeartbeatPinger {
+            configuration,
+            health,
+            client,
+            runtime,
+        })
+    }
+
+    pub fn start(&self, rx: Receiver<HeartbeatCommands>) {
+        let interval = Duration::from_secs(self.configuration.interval);
+        loop {
+            match rx.recv_timeout(interval) {
+                Ok(HeartbeatCommands::Stop) => {
+                    info!("Stopping Heartbeat");
+                    break;
+                }
+                Err(RecvTimeoutError::Timeout) => self.ping(),
+                Err(err) => {
+                    error!("{}", err);
+                    break;
+                }
+            }
+        }
+    }
+
+    fn ping(&self) {
+        if !self.health.is_healthy(self.configuration.healthy_within) {
+            warn!(
+                "No metric collected in the last {} seconds, skipping heartbeat",
+                self.configuration.healthy_within
+            );
+            return;
+        }
+        match self
+            .runtime
+            .block_on(Heartbeat::ping(&self.client, &self.configuration.name))
+        {
+            Ok(_) => debug!("Heartbeat {} pinged", self.configuration.name),
+            Err(err) => error!(
+                "Error occurred while pinging heartbeat {}: {}",
+                self.configuration.name, err
+            ),
+        }
+    }
+}
@@ -0,0 +1,386 @@
+use chrono::{DateTime, Local, TimeZone, Utc};
+use ldm_commons::history::{EventKind, HistoryCommands, HistoryEvent};
+use ldm_metrics::matcher::glob;
+use serde_derive::Deserialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Seconds between two retention passes over the history file.
+const COMPACT_INTERVAL: u64 = 60 * 60;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct HistoryConfiguration {
+    /// File the events are appended to, one json object per line.
+    pub path: Option<String>,
+    /// Seconds an event is kept.
+    #[serde(default = "default_max_age")]
+    pub max_age: i64,
+    /// Events kept at most, the oldest ones are dropped first.
+    #[serde(default = "default_max_events")]
+    pub max_events: usize,
+}
+
+fn default_max_age() -> i64 {
+    30 * 24 * 60 * 60
+}
+
+fn default_max_events() -> usize {
+    10_000
+}
+
+impl Default for HistoryConfiguration {
+    fn default() -> Self {
+        HistoryConfiguration {
+            path: None,
+            max_age: default_max_age(),
+            max_events: default_max_events(),
+        }
+    }
+}
+
+/// Filters of a history lookup, empty ones match every event.
+#[derive(Debug, Clone, Default)]
+pub struct HistoryQuery {
+    /// Alarm name, `*` matches any text.
+    pub alarm: Option<String>,
+    /// Metric name, `*` matches any text.
+    pub metric: Option<String>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    /// Returns only the latest events.
+    pub limit: Option<usize>,
+}
+
+impl HistoryQuery {
+    /// Reads `alarm`, `metric`, `since`, `until` and `limit`, times are unix seconds or RFC3339.
+    pub fn parse<I: IntoIterator<Item = (String, String)>>(
+        pairs: I,
+    ) -> Result<HistoryQuery, String> {
+        let mut query = HistoryQuery::default();
+        for (name, value) in pairs {
+            match name.as_str() {
+                "alarm" => query.alarm = Some(value),
+                "metric" => query.metric = Some(value),
+                "since" => query.since = Some(parse_time(&value)?),
+                "until" => query.until = Some(parse_time(&value)?),
+                "limit" => {
+                    query.limit = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("Invalid limit {}", value))?,
+                    )
+                }
+                _ => return Err(format!("Unknown filter {}", name)),
+            }
+        }
+        Ok(query)
+    }
+
+    fn matches(&self, event: &HistoryEvent) -> bool {
+        self.alarm
+            .as_ref()
+            .is_none_or(|alarm| glob(alarm, &event.alarm))
+            && self
+                .metric
+                .as_ref()
+                .is_none_or(|metric| glob(metric, &event.metric))
+            && self.since.is_none_or(|since| event.timestamp >= since)
+            && self.until.is_none_or(|until| event.timestamp <= until)
+    }
+}
+
+fn parse_time(value: &str) -> Result<i64, String> {
+    value
+        .parse::<i64>()
+        .or_else(|_| DateTime::parse_from_rfc3339(value).map(|time| time.timestamp()))
+        .map_err(|_| format!("Invalid time {}, expected unix seconds or RFC3339", value))
+}
+
+/// Append-only log of alarm transitions and deliveries, trimmed to the retention limits.
+#[derive(Debug, Clone)]
+pub struct AlarmHistory {
+    path: Option<PathBuf>,
+    max_age: i64,
+    max_events: usize,
+    lock: Arc<Mutex<()>>,
+}
+
+impl AlarmHistory {
+    pub fn new(configuration: &HistoryConfiguration) -> AlarmHistory {
+        AlarmHistory {
+            path: configuration.path.as_ref().map(PathBuf::from),
+            max_age: configuration.max_age,
+            max_events: configuration.max_events,
+            lock: Arc::default(),
+        }
+    }
+
+    /// Records the events sent over `rx` until `Stop` is received.
+    pub fn start(&self, rx: Receiver<HistoryCommands>) {
+        let interval = Duration::from_secs(COMPACT_INTERVAL);
+        self.compact();
+        let mut compacted = Instant::now();
+        loop {
+            match rx.recv_timeout(interval) {
+                Ok(HistoryCommands::Record(event)) => self.append(&event),
+                Ok(HistoryCommands::Stop) => {
+                    info!("Stopping History");
+                    break;
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(err) => {
+                    error!("{}", err);
+                    break;
+                }
+            }
+            // Also while events keep arriving, so the log is trimmed under load.
+            if compacted.elapsed() >= interval {
+                self.compact();
+                compacted = Instant::now();
+            }
+        }
+    }
+
+    /// Matching events, oldest first.
+    pub fn query(&self, query: &HistoryQuery) -> Vec<HistoryEvent> {
+        let _lock = self.lock.lock().unwrap();
+        let mut events: Vec<HistoryEvent> = self
+            .read()
+            .into_iter()
+            .filter(|event| query.matches(event))
+            .collect();
+        if let Some(limit) = query.limit {
+            events.drain(..events.len().saturating_sub(limit));
+        }
+        events
+    }
+
+    fn append(&self, event: &HistoryEvent) {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+        let _lock = self.lock.lock().unwrap();
+        let result = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|err| err.to_string())
+            .and_then(|mut file| {
+                let line = serde_json::to_string(event).map_err(|err| err.to_string())?;
+                writeln!(file, "{}", line).map_err(|err| err.to_string())
+            });
+        if let Err(err) = result {
+            error!("Error while writing alarm history {:?}: {}", path, err);
+        }
+    }
+
+    /// Drops the events past `max_age` and over `max_events`.
+    fn compact(&self) {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+        let _lock = self.lock.lock().unwrap();
+        let mut events = self.read();
+        let count = events.len();
+        let oldest = Utc::now().timestamp() - self.max_age;
+        events.retain(|event| event.timestamp >= oldest);
+        events.drain(..events.len().saturating_sub(self.max_events));
+        if events.len() == count {
+            return;
+        }
+        let mut temp = path.clone();
+        temp.set_extension("tmp");
+        let result = File::create(&temp)
+            .map_err(|err| err.to_string())
+            .and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                for event in &events {
+                    let line = serde_json::to_string(event).map_err(|err| err.to_string())?;
+                    writeln!(writer, "{}", line).map_err(|err| err.to_string())?;
+                }
+                writer.flush().map_err(|err| err.to_string())
+            })
+            .and_then(|_| fs::rename(&temp, path).map_err(|err| err.to_string()));
+        match result {
+            Ok(_) => info!("{} old history event(s) dropped", count - events.len()),
+            Err(err) => error!("Error while compacting alarm history {:?}: {}", path, err),
+        }
+    }
+
+    fn read(&self) -> Vec<HistoryEvent> {
+        let path = match &self.path {
+            Some(path) if path.exists() => path,
+            _ => return Vec::new(),
+        };
+        match File::open(path) {
+            Ok(file) => BufReader::new(file)
+                .lines()
+                .map_while(Result::ok)
+                .filter_map(|line| serde_json::from_str(&line).ok())
+                .collect(),
+            Err(err) => {
+                error!("Error while opening alarm history {:?}: {}", path, err);
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// Event as one line of text, used by the `history` subcommand.
+pub fn describe(event: &HistoryEvent) -> String {
+    let time = match Local.timestamp_opt(event.timestamp, 0).single() {
+        Some(time) => time.to_rfc3339(),
+        None => event.timestamp.to_string(),
+    };
+    let metric = match &event.dimension {
+        Some(dimension) if !event.metric.ends_with(dimension.as_str()) => {
+            format!("{} [{}]", event.metric, dimension)
+        }
+        _ => event.metric.clone(),
+    };
+    let what = match &event.kind {
+        EventKind::Transition { from, to, incident } => match incident {
+            Some(incident) => format!("{} -> {} ({})", from, to, incident),
+            None => format!("{} -> {}", from, to),
+        },
+        EventKind::Delivery {
+            sender,
+            delivered: true,
+            attempt,
+            ..
+        } => format!("sent to {} (attempt {})", sender, attempt),
+        EventKind::Delivery {
+            sender,
+            attempt,
+            error,
+            ..
+        } => format!(
+            "failed to send to {} (attempt {}): {}",
+            sender,
+            attempt,
+            error.as_deref().unwrap_or("")
+        ),
+    };
+    format!(
+        "{}  {}  {}  {}  {}",
+        time, event.alarm, metric, event.value, what
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(alarm: &str, metric: &str, timestamp: i64) -> HistoryEvent {
+        HistoryEvent {
+            timestamp,
+            alarm: String::from(alarm),
+            metric: String::from(metric),
+            dimension: None,
+            value: 0.9,
+            kind: EventKind::Transition {
+                from: String::from("Ok"),
+                to: String::from("Alarm"),
+                incident: None,
+            },
+        }
+    }
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (String::from(*name), String::from(*value)))
+            .collect()
+    }
+
+    fn history(name: &str, max_age: i64, max_events: usize) -> AlarmHistory {
+        let path =
+            std::env::temp_dir().join(format!("ldm_history_{}_{}.log", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        AlarmHistory::new(&HistoryConfiguration {
+            path: Some(path.to_string_lossy().to_string()),
+            max_age,
+            max_events,
+        })
+    }
+
+    #[test]
+    fn query_reads_unix_and_rfc3339_times() {
+        let query = HistoryQuery::parse(pairs(&[
+            ("alarm", "High*"),
+            ("since", "1700000000"),
+            ("until", "2023-11-15T00:13:20+01:00"),
+            ("limit", "5"),
+        ]))
+        .unwrap();
+        assert_eq!(query.alarm.as_deref(), Some("High*"));
+        assert_eq!(query.since, Some(1_700_000_000));
+        assert_eq!(query.until, Some(1_700_003_600));
+        assert_eq!(query.limit, Some(5));
+    }
+
+    #[test]
+    fn invalid_queries_are_rejected() {
+        assert_eq!(
+            HistoryQuery::parse(pairs(&[("limit", "many")])).unwrap_err(),
+            "Invalid limit many"
+        );
+        assert!(HistoryQuery::parse(pairs(&[("since", "yesterday")])).is_err());
+        assert_eq!(
+            HistoryQuery::parse(pairs(&[("sender", "slack")])).unwrap_err(),
+            "Unknown filter sender"
+        );
+    }
+
+    #[test]
+    fn query_matches_globs_and_times() {
+        let query = HistoryQuery {
+            alarm: Some(String::from("High *")),
+            metric: Some(String::from("disk::*")),
+            since: Some(100),
+            until: Some(200),
+            limit: None,
+        };
+        assert!(query.matches(&event("High usage", "disk::usage", 150)));
+        assert!(!query.matches(&event("Low usage", "disk::usage", 150)));
+        assert!(!query.matches(&event("High usage", "cpu::usage", 150)));
+        assert!(!query.matches(&event("High usage", "disk::usage", 250)));
+        assert!(HistoryQuery::default().matches(&event("Low usage", "cpu::usage", 0)));
+    }
+
+    #[test]
+    fn compact_drops_old_events() {
+        let history = history("max_age", 60, 10);
+        let now = Utc::now().timestamp();
+        history.append(&event("High CPU", "cpu::usage", now - 120));
+        history.append(&event("High CPU", "cpu::usage", now));
+        history.compact();
+        let events = history.query(&HistoryQuery::default());
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].timestamp, now);
+        let _ = fs::remove_file(history.path.unwrap());
+    }
+
+    #[test]
+    fn compact_keeps_the_latest_events() {
+        let history = history("max_events", 60, 2);
+        let now = Utc::now().timestamp();
+        for offset in 0..3 {
+            history.append(&event("High CPU", "cpu::usage", now + offset));
+        }
+        history.compact();
+        let timestamps: Vec<i64> = history
+            .query(&HistoryQuery::default())
+            .iter()
+            .map(|event| event.timestamp)
+            .collect();
+        assert_eq!(timestamps, vec![now + 1, now + 2]);
+        let _ = fs::remove_file(history.path.unwrap());
+    }
+}
@@ -1 +1,7 @@
-pub mod parser;
\ No newline at end of file
+#[macro_use]
+extern crate log;
+
+pub mod api;
+pub mod heartbeat;
+pub mod history;
+pub mod parser;
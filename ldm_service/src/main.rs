@@ -6,15 +6,25 @@ extern crate ldm_notifications;
 
 use signal_hook::{iterator::Signals, SIGTERM};
 
+use ldm_commons::history::HistoryCommands;
 use ldm_commons::{AlarmSenderCommands, MetricConsumerCommands};
+use ldm_metrics::ack::{AckRequest, Acknowledgement};
 use ldm_metrics::collector::{IncomingMessage, MetricCollector};
+use ldm_metrics::inhibit::Inhibitor;
+use ldm_metrics::replay::read_samples;
+use ldm_metrics::silence::Silences;
+use ldm_metrics::state::AlarmStates;
 use ldm_notifications::sender::AlarmSender;
-use ldm_service::parser::{get_config, get_config_dir};
+use ldm_service::api::{ApiConfiguration, ApiServer};
+use ldm_service::heartbeat::{HeartbeatCommands, HeartbeatPinger};
+use ldm_service::history::{describe, AlarmHistory, HistoryQuery};
+use ldm_service::parser::{get_config, get_config_dir, Config};
 use metric_consumer::consumer::MetricConsumer;
 use std::io::Error;
 use std::path::PathBuf;
 use std::sync::mpsc;
 use std::sync::mpsc::{Receiver, Sender};
+use tokio::runtime::Handle;
 
 #[tokio::main]
 async fn main() {
@@ -32,6 +42,34 @@ async fn main() {
             panic!("Error occurred while gating config.toml, {}", err);
         }
     };
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("replay") {
+        replay(config, &args[1..]);
+        return;
+    }
+    let mut history = config.history;
+    if history.path.is_none() {
+        history.path = get_config_dir().ok().map(|mut path| {
+            path.push("alarm_history.jsonl");
+            path.to_string_lossy().to_string()
+        });
+    }
+    let history = AlarmHistory::new(&history);
+    match args.first().map(String::as_str) {
+        Some("history") => {
+            print_history(&history, &args[1..]);
+            return;
+        }
+        Some("ack") => {
+            acknowledge(config.api.as_ref(), &args[1..]).await;
+            return;
+        }
+        _ => {}
+    }
+    let (history_tx, history_rx): (Sender<HistoryCommands>, Receiver<HistoryCommands>) =
+        mpsc::channel();
+    let recorder = history.clone();
+    std::thread::spawn(move || recorder.start(history_rx));
     let (notification_tx, notification_rx): (
         Sender<AlarmSenderCommands>,
         Receiver<AlarmSenderCommands>,
@@ -42,11 +80,62 @@ async fn main() {
     ) = mpsc::channel();
     let mut metric_consumer = MetricConsumer::new(metric_rx, config.consumers);
     std::thread::spawn(move || metric_consumer.start());
-    let mut metric_collector =
-        MetricCollector::new(notification_tx.clone(), metric_tx.clone(), config.metrics);
+    let silences = Silences::new(config.silences);
+    let mut state = config.state;
+    if state.path.is_none() {
+        state.path = get_config_dir().ok().map(|mut path| {
+            path.push("alarm_state.json");
+            path.to_string_lossy().to_string()
+        });
+    }
+    let mut metric_collector = MetricCollector::new(
+        notification_tx.clone(),
+        metric_tx.clone(),
+        config.metrics,
+        config.composites,
+        config.device.device(),
+        silences.clone(),
+        Inhibitor::new(config.inhibitions),
+    )
+    .state(AlarmStates::load(&state))
+    .history(history_tx.clone());
+    if let Some(api) = config.api {
+        match ApiServer::new(&api, silences, history, metric_collector.acknowledgements()) {
+            Ok(server) => {
+                info!("Api listening on {}", api.address);
+                std::thread::spawn(move || server.start());
+            }
+            Err(err) => error!("Error occurred while starting api {}", err),
+        }
+    }
+    let (heartbeat_tx, heartbeat_rx): (Sender<HeartbeatCommands>, Receiver<HeartbeatCommands>) =
+        mpsc::channel();
+    if let Some(heartbeat) = config.heartbeat {
+        match HeartbeatPinger::new(heartbeat, metric_collector.health(), Handle::current()) {
+            Ok(pinger) => {
+                std::thread::spawn(move || pinger.start(heartbeat_rx));
+            }
+            Err(err) => error!("Error occurred while building heartbeat client {}", err),
+        }
+    }
     let (tx, rx): (Sender<IncomingMessage>, Receiver<IncomingMessage>) = mpsc::channel();
     std::thread::spawn(move || metric_collector.start(rx));
-    let mut alarm_sender = AlarmSender::new(notification_rx, config.notifications);
+    let mut delivery = config.delivery;
+    if delivery.queue.is_none() {
+        delivery.queue = get_config_dir().ok().map(|mut path| {
+            path.push("notification_queue.json");
+            path.to_string_lossy().to_string()
+        });
+    }
+    let mut alarm_sender = match AlarmSender::new(
+        notification_rx,
+        config.notifications,
+        delivery,
+        Handle::current(),
+    ) {
+        Ok(alarm_sender) => alarm_sender.history(history_tx.clone()),
+        Err(err) => panic!("Error occurred while setting up notifications, {}", err),
+    };
     std::thread::spawn(move || alarm_sender.start());
     info!("Setup complete for {}", config.device);
     match Signals::new(&[SIGTERM]) {
@@ -54,16 +143,151 @@ async fn main() {
             for sig in signals.wait() {
                 if sig == SIGTERM {
                     tx.send(IncomingMessage::Stop);
+                    let _ = heartbeat_tx.send(HeartbeatCommands::Stop);
                     notification_tx.send(AlarmSenderCommands::Stop);
                     metric_tx.send(MetricConsumerCommands::Stop);
+                    let _ = history_tx.send(HistoryCommands::Stop);
                 }
             }
         }
         Err(err) => {
             error!("Error occurred while listening signals, closing : {}", err);
             tx.send(IncomingMessage::Stop);
+            let _ = heartbeat_tx.send(HeartbeatCommands::Stop);
             notification_tx.send(AlarmSenderCommands::Stop);
             metric_tx.send(MetricConsumerCommands::Stop);
+            let _ = history_tx.send(HistoryCommands::Stop);
+        }
+    }
+}
+
+/// `--name value` pairs of a subcommand.
+fn options(args: &[String]) -> Vec<(String, String)> {
+    args.chunks(2)
+        .map(|pair| {
+            (
+                pair[0].trim_start_matches("--").to_string(),
+                pair.get(1).cloned().unwrap_or_default(),
+            )
+        })
+        .collect()
+}
+
+fn exit_with(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+/// `ldm_service history [--alarm NAME] [--metric NAME] [--since TIME] [--until TIME] [--limit N]`
+fn print_history(history: &AlarmHistory, args: &[String]) {
+    match HistoryQuery::parse(options(args)) {
+        Ok(query) => {
+            for event in history.query(&query) {
+                println!("{}", describe(&event));
+            }
+        }
+        Err(err) => exit_with(&err),
+    }
+}
+
+/// `ldm_service replay FILE`, feeds recorded samples through the configured alarms and
+/// prints what would have been sent and the alarm transitions, nothing is sent.
+fn replay(config: Config, args: &[String]) {
+    let path = match args.first() {
+        Some(path) => PathBuf::from(path),
+        None => exit_with("Usage: ldm_service replay FILE"),
+    };
+    let samples = match read_samples(&path) {
+        Ok(samples) => samples,
+        Err(err) => exit_with(&err.to_string()),
+    };
+    let total = samples.len();
+    let (notification_tx, notification_rx) = mpsc::channel();
+    // Kept open so replayed samples are not logged as send errors.
+    let (metric_tx, _metric_rx) = mpsc::channel();
+    let (history_tx, history_rx) = mpsc::channel();
+    let mut collector = MetricCollector::new(
+        notification_tx.clone(),
+        metric_tx,
+        config.metrics,
+        config.composites,
+        config.device.device(),
+        Silences::new(config.silences),
+        Inhibitor::new(config.inhibitions),
+    )
+    .history(history_tx);
+    let replayed = collector.replay(samples);
+    let _ = notification_tx.send(AlarmSenderCommands::Stop);
+    let mut delivery = config.delivery;
+    delivery.queue = None;
+    match AlarmSender::new(
+        notification_rx,
+        config.notifications,
+        delivery,
+        Handle::current(),
+    ) {
+        Ok(alarm_sender) => alarm_sender.dry_run().start(),
+        Err(err) => exit_with(&err.to_string()),
+    }
+    println!("{} of {} samples replayed", replayed, total);
+    for command in history_rx.try_iter() {
+        if let HistoryCommands::Record(event) = command {
+            println!("{}", describe(&event));
+        }
+    }
+}
+
+/// `ldm_service ack [--alarm NAME] [--incident ID] [--comment TEXT] [--expires SECONDS]`,
+/// sent to the api of the running service.
+async fn acknowledge(api: Option<&ApiConfiguration>, args: &[String]) {
+    let address = match api {
+        Some(api) => &api.address,
+        None => exit_with("The api is not enabled, add an [api] section to config.toml"),
+    };
+    let mut request = AckRequest::default();
+    for (name, value) in options(args) {
+        match name.as_str() {
+            "alarm" => request.alarm = Some(value),
+            "incident" => request.incident = Some(value),
+            "comment" => request.comment = value,
+            "expires" => match value.parse() {
+                Ok(seconds) => request.expires = Some(seconds),
+                Err(_) => exit_with(&format!("Invalid expiry {}, expected seconds", value)),
+            },
+            _ => exit_with(&format!("Unknown option {}", name)),
+        }
+    }
+    let response = reqwest::Client::new()
+        .post(&format!("http://{}/alarms/ack", address))
+        .json(&request)
+        .send()
+        .await;
+    let response = match response {
+        Ok(response) => response,
+        Err(err) => exit_with(&format!(
+            "Error while reaching the api on {}: {}",
+            address, err
+        )),
+    };
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    if !status.is_success() {
+        let message = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|error| error["message"].as_str().map(String::from));
+        exit_with(&message.unwrap_or(body));
+    }
+    match serde_json::from_str::<Vec<Acknowledgement>>(&body) {
+        Ok(acknowledged) => {
+            for acknowledgement in acknowledged {
+                println!(
+                    "{}  {}  {}",
+                    acknowledgement.alarm,
+                    acknowledgement.metric,
+                    acknowledgement.incident.unwrap_or_default()
+                );
+            }
         }
+        Err(err) => exit_with(&format!("Invalid api response {}", err)),
     }
 }
@@ -1,10 +1,18 @@
+use crate::api::ApiConfiguration;
+use crate::heartbeat::HeartbeatConfiguration;
+use crate::history::HistoryConfiguration;
 use core::fmt;
+use ldm_commons::Device;
 use ldm_metrics::core::config::{AlarmConfiguration, MetricConfiguration};
-use ldm_notifications::core::config::NotificationConfiguration;
+use ldm_metrics::inhibit::InhibitRuleConfiguration;
+use ldm_metrics::rules::CompositeAlarmConfiguration;
+use ldm_metrics::silence::SilenceConfiguration;
+use ldm_metrics::state::StateConfiguration;
+use ldm_notifications::core::config::{DeliveryConfiguration, NotificationConfiguration};
 use metric_consumer::core::config::MetricConsumerConfiguration;
-use serde::export::fmt::Debug;
-use serde::export::Formatter;
 use serde_derive::Deserialize;
+use std::fmt::Debug;
+use std::fmt::Formatter;
 use std::fs::File;
 use std::io::{Error, ErrorKind, Read};
 use std::path::PathBuf;
@@ -65,8 +73,22 @@ fn get_config_home() -> Option<PathBuf> {
 pub struct Config {
     pub device: DeviceConf,
     pub metrics: Vec<MetricConfiguration>,
+    #[serde(default)]
+    pub composites: Vec<CompositeAlarmConfiguration>,
     pub notifications: Vec<NotificationConfiguration>,
+    #[serde(default)]
+    pub delivery: DeliveryConfiguration,
     pub consumers: Vec<MetricConsumerConfiguration>,
+    pub heartbeat: Option<HeartbeatConfiguration>,
+    #[serde(default)]
+    pub silences: Vec<SilenceConfiguration>,
+    #[serde(default)]
+    pub inhibitions: Vec<InhibitRuleConfiguration>,
+    pub api: Option<ApiConfiguration>,
+    #[serde(default)]
+    pub state: StateConfiguration,
+    #[serde(default)]
+    pub history: HistoryConfiguration,
 }
 
 #[derive(Deserialize, Debug)]
@@ -75,6 +97,15 @@ pub struct DeviceConf {
     ip: String,
 }
 
+impl DeviceConf {
+    pub fn device(&self) -> Device {
+        Device {
+            name: self.name.clone(),
+            ip: self.ip.clone(),
+        }
+    }
+}
+
 impl fmt::Display for DeviceConf {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "Device : '{}' [{}]", self.name, self.ip)
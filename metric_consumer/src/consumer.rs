@@ -1,5 +1,9 @@
 use crate::core::config::{Consumer, MetricConsumerConfiguration};
 use crate::file::config::FileConsumer;
+use crate::journal::config::JournalConsumer;
+use crate::syslog::config::SyslogConsumer;
+use ldm_commons::journal::JournalWriter;
+use ldm_commons::syslog::SyslogWriter;
 use ldm_commons::MetricConsumerCommands;
 use std::sync::mpsc::Receiver;
 
@@ -47,9 +51,17 @@ impl MetricConsumer {
         let mut consumers: Vec<Box<dyn Consumer>> = Vec::new();
         for conf in &self.configuration {
             match conf {
-                MetricConsumerConfiguration::File(f) => {
-                    consumers.push(Box::new(FileConsumer::new()))
+                MetricConsumerConfiguration::File(conf) => {
+                    consumers.push(Box::new(FileConsumer::new(conf)))
                 }
+                MetricConsumerConfiguration::Syslog(conf) => match SyslogWriter::new(conf) {
+                    Ok(writer) => consumers.push(Box::new(SyslogConsumer::new(writer))),
+                    Err(err) => error!("Error occurred while opening syslog socket {}", err),
+                },
+                MetricConsumerConfiguration::Journal(conf) => match JournalWriter::new(conf) {
+                    Ok(writer) => consumers.push(Box::new(JournalConsumer::new(writer))),
+                    Err(err) => error!("Error occurred while opening journal socket {}", err),
+                },
             }
         }
         consumers
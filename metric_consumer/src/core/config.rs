@@ -1,4 +1,6 @@
 use crate::file::config::FileConsumerConfiguration;
+use ldm_commons::journal::JournalConfiguration;
+use ldm_commons::syslog::SyslogConfiguration;
 use ldm_commons::MetricData;
 use serde::Deserialize;
 use std::fmt::Debug;
@@ -8,6 +10,10 @@ use std::fmt::Debug;
 pub enum MetricConsumerConfiguration {
     #[serde(rename = "file")]
     File(FileConsumerConfiguration),
+    #[serde(rename = "syslog")]
+    Syslog(SyslogConfiguration),
+    #[serde(rename = "journal")]
+    Journal(JournalConfiguration),
 }
 
 pub trait Consumer: Debug + Send + Sync {
@@ -1,24 +1,85 @@
 use crate::core::config::Consumer;
 use ldm_commons::MetricData;
 use serde::Deserialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
+/// Appends samples to `path` as json lines, the recordings `ldm_service replay` reads.
 #[derive(Deserialize, Debug)]
 pub struct FileConsumerConfiguration {
     path: String,
+    /// Rotated files kept next to `path`, as `path.1` (newest) to `path.N`.
     rotation: u32,
+    /// Bytes after which the file is rotated.
+    #[serde(default = "default_max_size")]
+    max_size: u64,
+}
+
+fn default_max_size() -> u64 {
+    10 * 1024 * 1024
 }
 
 #[derive(Debug)]
-pub struct FileConsumer {}
+pub struct FileConsumer {
+    path: PathBuf,
+    rotation: u32,
+    max_size: u64,
+}
 
 impl FileConsumer {
-    pub fn new() -> FileConsumer {
-        FileConsumer {}
+    pub fn new(conf: &FileConsumerConfiguration) -> FileConsumer {
+        FileConsumer {
+            path: PathBuf::from(&conf.path),
+            rotation: conf.rotation,
+            max_size: conf.max_size,
+        }
     }
+
+    fn rotate(&self) -> std::io::Result<()> {
+        let rotated = |index: u32| -> PathBuf {
+            let mut name = self.path.clone().into_os_string();
+            name.push(format!(".{}", index));
+            PathBuf::from(name)
+        };
+        if self.rotation == 0 {
+            return fs::remove_file(&self.path);
+        }
+        for index in (1..self.rotation).rev() {
+            let from = rotated(index);
+            if from.exists() {
+                fs::rename(&from, rotated(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated(1))
+    }
+}
+
+fn size(path: &Path) -> u64 {
+    fs::metadata(path).map(|meta| meta.len()).unwrap_or(0)
 }
 
 impl Consumer for FileConsumer {
     fn consume(&self, metric: MetricData) {
-        unimplemented!()
+        if size(&self.path) >= self.max_size {
+            if let Err(err) = self.rotate() {
+                error!("Error occurred while rotating {:?} {}", self.path, err);
+            }
+        }
+        let result = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|err| err.to_string())
+            .and_then(|mut file| {
+                let line = serde_json::to_string(&metric).map_err(|err| err.to_string())?;
+                writeln!(file, "{}", line).map_err(|err| err.to_string())
+            });
+        if let Err(err) = result {
+            error!(
+                "Error occurred while writing sample to {:?} {}",
+                self.path, err
+            );
+        }
     }
 }
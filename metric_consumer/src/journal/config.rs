@@ -0,0 +1,28 @@
+use crate::core::config::Consumer;
+use ldm_commons::journal::JournalWriter;
+use ldm_commons::syslog::Severity;
+use ldm_commons::MetricData;
+
+#[derive(Debug)]
+pub struct JournalConsumer {
+    writer: JournalWriter,
+}
+
+impl JournalConsumer {
+    pub fn new(writer: JournalWriter) -> JournalConsumer {
+        JournalConsumer { writer }
+    }
+}
+
+impl Consumer for JournalConsumer {
+    fn consume(&self, metric: MetricData) {
+        let fields = [
+            ("METRIC", String::from(metric.name())),
+            ("VALUE", metric.value().to_string()),
+        ];
+        let message = format!("{} {}", metric.name(), metric.value());
+        if let Err(err) = self.writer.write(Severity::Info as u8, &message, &fields) {
+            error!("Error occurred while writing sample to journal {}", err);
+        }
+    }
+}
@@ -1,6 +1,8 @@
 pub mod consumer;
 pub mod core;
 pub mod file;
+pub mod journal;
+pub mod syslog;
 
 #[macro_use]
 extern crate log;
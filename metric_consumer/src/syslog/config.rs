@@ -0,0 +1,30 @@
+use crate::core::config::Consumer;
+use ldm_commons::syslog::{Severity, SyslogWriter};
+use ldm_commons::MetricData;
+
+#[derive(Debug)]
+pub struct SyslogConsumer {
+    writer: SyslogWriter,
+}
+
+impl SyslogConsumer {
+    pub fn new(writer: SyslogWriter) -> SyslogConsumer {
+        SyslogConsumer { writer }
+    }
+}
+
+impl Consumer for SyslogConsumer {
+    fn consume(&self, metric: MetricData) {
+        let params = [
+            ("metric", String::from(metric.name())),
+            ("value", metric.value().to_string()),
+        ];
+        let message = format!("{} {}", metric.name(), metric.value());
+        if let Err(err) = self
+            .writer
+            .write(Severity::Info, "sample", &params, &message)
+        {
+            error!("Error occurred while writing sample to syslog {}", err);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -31,7 +32,7 @@ pub struct AlertStatus {
     pub alias: String,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Priority {
     P1,
     P2,
@@ -40,11 +41,19 @@ pub enum Priority {
     P5,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Responder {
-    id: String,
-    r#type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Name of a team, escalation or schedule.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Username of a user.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// One of team, user, escalation and schedule.
+    pub r#type: String,
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -80,7 +89,7 @@ pub struct AlertData {
     pub tags: Option<Vec<String>>,
     /// Map of key-value pairs to use as custom properties of the alert.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub details: Option<String>, // TODO: that should be key value pair.(Use HashMap)
+    pub details: Option<HashMap<String, String>>,
     /// Entity field of the alert that is generally used to specify which domain alert is related to.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub entity: Option<String>,
@@ -120,6 +129,18 @@ impl AlertData {
         self
     }
 
+    pub fn description(mut self, description: String) -> AlertData {
+        self.description = Some(description);
+        self
+    }
+    pub fn responders(mut self, responders: Vec<Responder>) -> AlertData {
+        self.responders = Some(responders);
+        self
+    }
+    pub fn details(mut self, details: HashMap<String, String>) -> AlertData {
+        self.details = Some(details);
+        self
+    }
     pub fn tags(mut self, tags: Vec<String>) -> AlertData {
         self.tags = Some(tags.clone());
         self
@@ -137,3 +158,207 @@ impl AlertData {
         self
     }
 }
+
+/// Response of the asynchronous alert actions, status can be followed with the request id.
+pub type AlertActionResponse = AlertCreateResponse<String>;
+
+/// Identifies an alert by id, alias or tiny id.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Identifier {
+    Id(String),
+    Alias(String),
+    TinyId(String),
+}
+
+impl Identifier {
+    pub fn value(&self) -> &str {
+        match self {
+            Identifier::Id(value) | Identifier::Alias(value) | Identifier::TinyId(value) => value,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Identifier::Id(_) => "id",
+            Identifier::Alias(_) => "alias",
+            Identifier::TinyId(_) => "tiny",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionData {
+    /// Display name of the request owner.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Display name of the request source.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Additional note that will be added while executing the action.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnoozeData {
+    /// Date and time in ISO 8601 format until the alert is snoozed.
+    pub end_time: String,
+    #[serde(flatten)]
+    pub action: ActionData,
+}
+
+/// Escalation or user, either id or name (username for users) should be given.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Recipient {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EscalateData {
+    pub escalation: Recipient,
+    #[serde(flatten)]
+    pub action: ActionData,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssignData {
+    pub owner: Recipient,
+    #[serde(flatten)]
+    pub action: ActionData,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagsData {
+    pub tags: Vec<String>,
+    #[serde(flatten)]
+    pub action: ActionData,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetailsData {
+    pub details: HashMap<String, String>,
+    #[serde(flatten)]
+    pub action: ActionData,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Integration {
+    pub id: String,
+    pub name: String,
+    pub r#type: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AlertReport {
+    pub ack_time: Option<u64>,
+    pub close_time: Option<u64>,
+    pub acknowledged_by: Option<String>,
+    pub closed_by: Option<String>,
+}
+
+/// Alert as returned by get and list, list results don't contain actions, entity, description and details.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AlertInfo {
+    pub id: String,
+    pub tiny_id: String,
+    pub alias: String,
+    pub message: String,
+    pub status: String,
+    pub acknowledged: bool,
+    pub is_seen: bool,
+    pub tags: Vec<String>,
+    pub snoozed: bool,
+    pub snoozed_until: Option<String>,
+    pub count: u64,
+    pub last_occurred_at: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub source: String,
+    pub owner: String,
+    pub priority: Option<Priority>,
+    pub responders: Vec<Responder>,
+    pub integration: Option<Integration>,
+    pub report: Option<AlertReport>,
+    pub actions: Vec<String>,
+    pub entity: Option<String>,
+    pub description: Option<String>,
+    pub details: HashMap<String, String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Paging {
+    pub first: Option<String>,
+    pub next: Option<String>,
+    pub prev: Option<String>,
+    pub last: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertListResponse {
+    pub data: Vec<AlertInfo>,
+    #[serde(default)]
+    pub paging: Paging,
+    pub took: f32,
+    pub request_id: String,
+}
+
+/// Search parameters of the list call, `query` uses the Opsgenie search syntax.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ListQuery {
+    pub query: Option<String>,
+    /// Saved search id or name, `search_identifier_type` is "id" or "name".
+    pub search_identifier: Option<String>,
+    pub search_identifier_type: Option<String>,
+    pub offset: Option<u32>,
+    /// Maximum number of items, Opsgenie allows at most 100.
+    pub limit: Option<u32>,
+    /// Field to sort by, e.g. createdAt.
+    pub sort: Option<String>,
+    /// asc or desc.
+    pub order: Option<String>,
+}
+
+impl ListQuery {
+    pub fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+        if let Some(query) = &self.query {
+            params.push(("query", query.clone()));
+        }
+        if let Some(identifier) = &self.search_identifier {
+            params.push(("searchIdentifier", identifier.clone()));
+        }
+        if let Some(identifier_type) = &self.search_identifier_type {
+            params.push(("searchIdentifierType", identifier_type.clone()));
+        }
+        if let Some(offset) = self.offset {
+            params.push(("offset", offset.to_string()));
+        }
+        if let Some(limit) = self.limit {
+            params.push(("limit", limit.to_string()));
+        }
+        if let Some(sort) = &self.sort {
+            params.push(("sort", sort.clone()));
+        }
+        if let Some(order) = &self.order {
+            params.push(("order", order.clone()));
+        }
+        params
+    }
+}
@@ -1,62 +1,239 @@
-use crate::alert::models::{AlertCreateResponse, AlertData, AlertStatus, AlertStatusResponse};
-use reqwest::header::AUTHORIZATION;
-use reqwest::Client;
+use crate::alert::models::{
+    ActionData, AlertActionResponse, AlertCreateResponse, AlertData, AlertInfo, AlertListResponse,
+    AlertStatus, AlertStatusResponse, AssignData, DetailsData, EscalateData, Identifier, ListQuery,
+    SnoozeData, TagsData,
+};
+use crate::client::OpsgenieClient;
+use crate::errors::OpsgenieError;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use reqwest::Method;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+
+const ALERTS_PATH: &str = "/v2/alerts";
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Alert {
     request_id: String,
-    key: String,
     alert_status: Option<AlertStatus>,
 }
+
+/// Aliases are free text, so the identifier is encoded as a single path segment.
+fn alert_url(client: &OpsgenieClient, identifier: &Identifier, action: &str) -> String {
+    client.url(&format!(
+        "{}/{}{}",
+        ALERTS_PATH,
+        utf8_percent_encode(identifier.value(), NON_ALPHANUMERIC),
+        action
+    ))
+}
+
 impl Alert {
-    pub fn create(key: &String, data: AlertData) -> Result<Alert, reqwest::Error> {
-        let request_url = "https://api.opsgenie.com/v2/alerts";
-        let mut response = Client::new()
-            .post(request_url)
-            .header(AUTHORIZATION, format!("GenieKey {}", key))
-            .json(&data)
-            .send()?;
-        let resp: AlertCreateResponse<String> = response.json()?;
+    pub async fn create(client: &OpsgenieClient, data: AlertData) -> Result<Alert, OpsgenieError> {
+        let resp: AlertCreateResponse<String> = client
+            .execute(
+                client
+                    .request(Method::POST, &client.url(ALERTS_PATH))
+                    .json(&data),
+            )
+            .await?;
         Ok(Alert {
             request_id: resp.request_id,
-            key: key.clone(),
             alert_status: None,
         })
     }
-    pub fn status(mut self) -> Result<Option<AlertStatus>, reqwest::Error> {
-        let request_url = format!(
-            "https://api.opsgenie.com/v2/alerts/requests/{}",
-            self.request_id
-        );
-        let mut response = Client::new()
-            .get(&request_url)
-            .header(AUTHORIZATION, format!("GenieKey {}", self.key))
-            .send()?;
-        let resp: AlertStatusResponse<AlertStatus> = response.json()?;
+    pub fn request_id(&self) -> &str {
+        &self.request_id
+    }
+    pub async fn status(
+        mut self,
+        client: &OpsgenieClient,
+    ) -> Result<Option<AlertStatus>, OpsgenieError> {
+        let request_url = client.url(&format!("{}/requests/{}", ALERTS_PATH, self.request_id));
+        let resp: AlertStatusResponse<AlertStatus> = client
+            .execute(client.request(Method::GET, &request_url))
+            .await?;
         self.alert_status = Some(resp.data);
         Ok(self.alert_status)
     }
-    pub fn close(&mut self) -> Result<(), reqwest::Error> {
-        let request_url = format!(
-            "https://api.opsgenie.com/v2/alerts/requests/{}",
-            self.request_id
-        );
-        let mut response = Client::new()
-            .get(&request_url)
-            .header(AUTHORIZATION, format!("GenieKey {}", self.key))
-            .send()?;
-        let resp: AlertStatusResponse<AlertStatus> = response.json()?;
-        let request_url = format!(
-            "https://api.opsgenie.com/v2/alerts/{}/close",
-            resp.data.alert_id
-        );
-        Client::new()
-            .post(&request_url)
-            .header(AUTHORIZATION, format!("GenieKey {}", self.key))
-            .json(&json!({}))
-            .send()?;
-
+    pub async fn close(&mut self, client: &OpsgenieClient) -> Result<(), OpsgenieError> {
+        let request_url = client.url(&format!("{}/requests/{}", ALERTS_PATH, self.request_id));
+        let resp: AlertStatusResponse<AlertStatus> = client
+            .execute(client.request(Method::GET, &request_url))
+            .await?;
+        Alert::close_by(
+            client,
+            &Identifier::Id(resp.data.alert_id),
+            ActionData::default(),
+        )
+        .await?;
         Ok(())
     }
+
+    pub async fn get(
+        client: &OpsgenieClient,
+        identifier: &Identifier,
+    ) -> Result<AlertInfo, OpsgenieError> {
+        let resp: AlertStatusResponse<AlertInfo> = client
+            .execute(
+                client
+                    .request(Method::GET, &alert_url(client, identifier, ""))
+                    .query(&[("identifierType", identifier.kind())]),
+            )
+            .await?;
+        Ok(resp.data)
+    }
+
+    /// One page of alerts, use `offset` and `limit` of the query to walk the pages.
+    pub async fn list(
+        client: &OpsgenieClient,
+        query: &ListQuery,
+    ) -> Result<AlertListResponse, OpsgenieError> {
+        client
+            .execute(
+                client
+                    .request(Method::GET, &client.url(ALERTS_PATH))
+                    .query(&query.params()),
+            )
+            .await
+    }
+
+    pub async fn close_by(
+        client: &OpsgenieClient,
+        identifier: &Identifier,
+        data: ActionData,
+    ) -> Result<Alert, OpsgenieError> {
+        Alert::action(client, identifier, "/close", &data).await
+    }
+
+    pub async fn acknowledge(
+        client: &OpsgenieClient,
+        identifier: &Identifier,
+        data: ActionData,
+    ) -> Result<Alert, OpsgenieError> {
+        Alert::action(client, identifier, "/acknowledge", &data).await
+    }
+
+    pub async fn unacknowledge(
+        client: &OpsgenieClient,
+        identifier: &Identifier,
+        data: ActionData,
+    ) -> Result<Alert, OpsgenieError> {
+        Alert::action(client, identifier, "/unacknowledge", &data).await
+    }
+
+    pub async fn snooze(
+        client: &OpsgenieClient,
+        identifier: &Identifier,
+        data: SnoozeData,
+    ) -> Result<Alert, OpsgenieError> {
+        Alert::action(client, identifier, "/snooze", &data).await
+    }
+
+    pub async fn escalate(
+        client: &OpsgenieClient,
+        identifier: &Identifier,
+        data: EscalateData,
+    ) -> Result<Alert, OpsgenieError> {
+        Alert::action(client, identifier, "/escalate", &data).await
+    }
+
+    pub async fn assign(
+        client: &OpsgenieClient,
+        identifier: &Identifier,
+        data: AssignData,
+    ) -> Result<Alert, OpsgenieError> {
+        Alert::action(client, identifier, "/assign", &data).await
+    }
+
+    pub async fn add_tags(
+        client: &OpsgenieClient,
+        identifier: &Identifier,
+        data: TagsData,
+    ) -> Result<Alert, OpsgenieError> {
+        Alert::action(client, identifier, "/tags", &data).await
+    }
+
+    pub async fn remove_tags(
+        client: &OpsgenieClient,
+        identifier: &Identifier,
+        tags: &[String],
+        data: ActionData,
+    ) -> Result<Alert, OpsgenieError> {
+        let mut params = vec![
+            ("identifierType", String::from(identifier.kind())),
+            ("tags", tags.join(",")),
+        ];
+        if let Some(user) = data.user {
+            params.push(("user", user));
+        }
+        if let Some(source) = data.source {
+            params.push(("source", source));
+        }
+        if let Some(note) = data.note {
+            params.push(("note", note));
+        }
+        let resp = client
+            .execute(
+                client
+                    .request(Method::DELETE, &alert_url(client, identifier, "/tags"))
+                    .query(&params),
+            )
+            .await?;
+        Ok(Alert::accepted(resp))
+    }
+
+    pub async fn add_note(
+        client: &OpsgenieClient,
+        identifier: &Identifier,
+        data: ActionData,
+    ) -> Result<Alert, OpsgenieError> {
+        Alert::action(client, identifier, "/notes", &data).await
+    }
+
+    pub async fn add_details(
+        client: &OpsgenieClient,
+        identifier: &Identifier,
+        data: DetailsData,
+    ) -> Result<Alert, OpsgenieError> {
+        Alert::action(client, identifier, "/details", &data).await
+    }
+
+    async fn action<T: Serialize>(
+        client: &OpsgenieClient,
+        identifier: &Identifier,
+        action: &str,
+        data: &T,
+    ) -> Result<Alert, OpsgenieError> {
+        let resp = client
+            .execute(
+                client
+                    .request(Method::POST, &alert_url(client, identifier, action))
+                    .query(&[("identifierType", identifier.kind())])
+                    .json(data),
+            )
+            .await?;
+        Ok(Alert::accepted(resp))
+    }
+
+    fn accepted(resp: AlertActionResponse) -> Alert {
+        Alert {
+            request_id: resp.request_id,
+            alert_status: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alias_is_encoded_in_path() {
+        let client = OpsgenieClient::new("key").unwrap();
+        let identifier = Identifier::Alias(String::from("disk /home@1"));
+        assert_eq!(
+            alert_url(&client, &identifier, "/acknowledge"),
+            "https://api.opsgenie.com/v2/alerts/disk%20%2Fhome%401/acknowledge"
+        );
+    }
 }
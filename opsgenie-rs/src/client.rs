@@ -0,0 +1,298 @@
+use crate::errors::OpsgenieError;
+use reqwest::header::{AUTHORIZATION, RETRY_AFTER};
+use reqwest::{Client, Method, Proxy, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::time::Duration;
+
+const US_URL: &str = "https://api.opsgenie.com";
+const EU_URL: &str = "https://api.eu.opsgenie.com";
+/// Rate limited requests are not retried when the server asks to wait longer than this.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Region {
+    #[default]
+    Us,
+    Eu,
+}
+
+impl Region {
+    pub fn base_url(self) -> &'static str {
+        match self {
+            Region::Us => US_URL,
+            Region::Eu => EU_URL,
+        }
+    }
+}
+
+/// Connection settings of an `OpsgenieClient`, can be embedded in configuration files.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ClientConfiguration {
+    #[serde(default)]
+    pub region: Region,
+    /// Overrides the region, e.g. for a proxy or a local mock server.
+    pub base_url: Option<String>,
+    /// Request timeout in seconds.
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+    #[serde(default = "default_connect_timeout")]
+    pub connect_timeout: u64,
+    pub proxy: Option<String>,
+    /// Extra attempts for rate limited (429) and failed (5xx) requests.
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+    /// Seconds before the first retry, doubled on each attempt up to a minute.
+    #[serde(default = "default_retry_backoff")]
+    pub retry_backoff: u64,
+}
+
+fn default_timeout() -> u64 {
+    30
+}
+
+fn default_connect_timeout() -> u64 {
+    10
+}
+
+fn default_retries() -> u32 {
+    3
+}
+
+fn default_retry_backoff() -> u64 {
+    1
+}
+
+impl Default for ClientConfiguration {
+    fn default() -> Self {
+        ClientConfiguration {
+            region: Region::default(),
+            base_url: None,
+            timeout: default_timeout(),
+            connect_timeout: default_connect_timeout(),
+            proxy: None,
+            retries: default_retries(),
+            retry_backoff: default_retry_backoff(),
+        }
+    }
+}
+
+impl ClientConfiguration {
+    pub fn client(&self, key: &str) -> Result<OpsgenieClient, OpsgenieError> {
+        let mut builder = OpsgenieClient::builder(key)
+            .region(self.region)
+            .timeout(Duration::from_secs(self.timeout))
+            .connect_timeout(Duration::from_secs(self.connect_timeout))
+            .retries(self.retries, Duration::from_secs(self.retry_backoff));
+        if let Some(base_url) = &self.base_url {
+            builder = builder.base_url(base_url);
+        }
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(proxy);
+        }
+        builder.build()
+    }
+}
+
+/// Authenticated Opsgenie API client. Cloning is cheap and shares the connection pool.
+#[derive(Clone, Debug)]
+pub struct OpsgenieClient {
+    http: Client,
+    base_url: String,
+    key: String,
+    retries: u32,
+    retry_backoff: Duration,
+}
+
+/// Error body returned by the API.
+#[derive(Deserialize)]
+struct ErrorResponse {
+    message: String,
+}
+
+impl OpsgenieClient {
+    pub fn new(key: &str) -> Result<OpsgenieClient, OpsgenieError> {
+        OpsgenieClient::builder(key).build()
+    }
+
+    pub fn builder(key: &str) -> OpsgenieClientBuilder {
+        OpsgenieClientBuilder {
+            key: String::from(key),
+            base_url: String::from(US_URL),
+            timeout: None,
+            connect_timeout: None,
+            proxy: None,
+            retries: default_retries(),
+            retry_backoff: Duration::from_secs(default_retry_backoff()),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Full url of an API path such as `/v2/alerts`.
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    pub fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        self.http
+            .request(method, url)
+            .header(AUTHORIZATION, format!("GenieKey {}", self.key))
+    }
+
+    /// Sends the request and decodes a successful response. Rate limited and
+    /// server errors are retried with exponential backoff, honoring Retry-After.
+    pub async fn execute<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<T, OpsgenieError> {
+        let mut attempt = 0;
+        loop {
+            let retry = match request.try_clone() {
+                Some(retry) => retry,
+                None => return send(request).await,
+            };
+            let err = match send(retry).await {
+                Ok(data) => return Ok(data),
+                Err(err) => err,
+            };
+            if attempt >= self.retries || !err.is_retryable() {
+                return Err(err);
+            }
+            let delay = match err {
+                OpsgenieError::RateLimited {
+                    retry_after: Some(retry_after),
+                } if retry_after > MAX_RETRY_AFTER => return Err(err),
+                OpsgenieError::RateLimited {
+                    retry_after: Some(retry_after),
+                } => retry_after,
+                _ => backoff(self.retry_backoff, attempt),
+            };
+            attempt += 1;
+            tokio::time::delay_for(delay).await;
+        }
+    }
+}
+
+/// Delay before the retry after `attempt` failed retries, capped so many retries cannot overflow.
+fn backoff(first: Duration, attempt: u32) -> Duration {
+    first
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_BACKOFF)
+}
+
+async fn send<T: DeserializeOwned>(request: RequestBuilder) -> Result<T, OpsgenieError> {
+    let response = request.send().await?;
+    if response.status().is_success() {
+        return Ok(response.json().await?);
+    }
+    Err(error_of(response).await)
+}
+
+async fn error_of(response: Response) -> OpsgenieError {
+    let status = response.status().as_u16();
+    let retry_after = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs);
+    let body = response.text().await.unwrap_or_default();
+    let message = match serde_json::from_str::<ErrorResponse>(&body) {
+        Ok(resp) => resp.message,
+        Err(_) => body,
+    };
+    match status {
+        401 | 403 => OpsgenieError::Auth { status, message },
+        400 | 422 => OpsgenieError::Validation { status, message },
+        404 => OpsgenieError::NotFound(message),
+        429 => OpsgenieError::RateLimited { retry_after },
+        500..=599 => OpsgenieError::Server { status, message },
+        _ => OpsgenieError::Unexpected { status, message },
+    }
+}
+
+#[derive(Debug)]
+pub struct OpsgenieClientBuilder {
+    key: String,
+    base_url: String,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxy: Option<String>,
+    retries: u32,
+    retry_backoff: Duration,
+}
+
+impl OpsgenieClientBuilder {
+    pub fn region(mut self, region: Region) -> Self {
+        self.base_url = String::from(region.base_url());
+        self
+    }
+
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = String::from(base_url.trim_end_matches('/'));
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Proxy url used for every request, e.g. `http://proxy:3128`.
+    pub fn proxy(mut self, proxy: &str) -> Self {
+        self.proxy = Some(String::from(proxy));
+        self
+    }
+
+    /// Extra attempts for 429 and 5xx responses and the delay before the first one.
+    pub fn retries(mut self, retries: u32, backoff: Duration) -> Self {
+        self.retries = retries;
+        self.retry_backoff = backoff;
+        self
+    }
+
+    pub fn build(self) -> Result<OpsgenieClient, OpsgenieError> {
+        let mut builder = Client::builder();
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(Proxy::all(proxy.as_str())?);
+        }
+        Ok(OpsgenieClient {
+            http: builder.build()?,
+            base_url: self.base_url,
+            key: self.key,
+            retries: self.retries,
+            retry_backoff: self.retry_backoff,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let first = Duration::from_secs(1);
+        assert_eq!(backoff(first, 0), first);
+        assert_eq!(backoff(first, 3), Duration::from_secs(8));
+        assert_eq!(backoff(first, 6), MAX_BACKOFF);
+        assert_eq!(backoff(first, 40), MAX_BACKOFF);
+        assert_eq!(backoff(Duration::from_secs(u64::MAX), 1), MAX_BACKOFF);
+    }
+}
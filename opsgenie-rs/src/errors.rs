@@ -0,0 +1,100 @@
+use std::error;
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug)]
+pub enum OpsgenieError {
+    /// 401 or 403, the key is wrong or lacks the needed rights.
+    Auth {
+        status: u16,
+        message: String,
+    },
+    /// 400 or 422, the request was rejected.
+    Validation {
+        status: u16,
+        message: String,
+    },
+    /// 429, `retry_after` is taken from the Retry-After header when present.
+    RateLimited {
+        retry_after: Option<Duration>,
+    },
+    NotFound(String),
+    Server {
+        status: u16,
+        message: String,
+    },
+    /// Any other unexpected status.
+    Unexpected {
+        status: u16,
+        message: String,
+    },
+    /// Connection, timeout or body decoding failures.
+    Transport(reqwest::Error),
+}
+
+impl OpsgenieError {
+    /// Whether sending the same request again may succeed.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            OpsgenieError::RateLimited { .. } | OpsgenieError::Server { .. } => true,
+            OpsgenieError::Transport(err) => err.is_timeout() || err.is_connect(),
+            _ => false,
+        }
+    }
+
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            OpsgenieError::Auth { status, .. }
+            | OpsgenieError::Validation { status, .. }
+            | OpsgenieError::Server { status, .. }
+            | OpsgenieError::Unexpected { status, .. } => Some(*status),
+            OpsgenieError::RateLimited { .. } => Some(429),
+            OpsgenieError::NotFound(_) => Some(404),
+            OpsgenieError::Transport(err) => err.status().map(|status| status.as_u16()),
+        }
+    }
+}
+
+impl fmt::Display for OpsgenieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpsgenieError::Auth { status, message } => {
+                write!(f, "Authentication failed ({}): {}", status, message)
+            }
+            OpsgenieError::Validation { status, message } => {
+                write!(f, "Request rejected ({}): {}", status, message)
+            }
+            OpsgenieError::RateLimited {
+                retry_after: Some(retry_after),
+            } => write!(
+                f,
+                "Rate limited, retry after {} seconds",
+                retry_after.as_secs()
+            ),
+            OpsgenieError::RateLimited { retry_after: None } => write!(f, "Rate limited"),
+            OpsgenieError::NotFound(message) => write!(f, "Not found: {}", message),
+            OpsgenieError::Server { status, message } => {
+                write!(f, "Server error ({}): {}", status, message)
+            }
+            OpsgenieError::Unexpected { status, message } => {
+                write!(f, "Unexpected response ({}): {}", status, message)
+            }
+            OpsgenieError::Transport(err) => write!(f, "Transport error: {}", err),
+        }
+    }
+}
+
+impl error::Error for OpsgenieError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            OpsgenieError::Transport(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for OpsgenieError {
+    fn from(err: reqwest::Error) -> Self {
+        OpsgenieError::Transport(err)
+    }
+}
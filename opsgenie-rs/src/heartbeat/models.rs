@@ -0,0 +1,136 @@
+use crate::alert::models::Priority;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeartbeatResponse<T> {
+    pub data: T,
+    pub took: f32,
+    pub request_id: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PingResponse {
+    pub result: String,
+    pub took: f32,
+    pub request_id: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum IntervalUnit {
+    #[serde(rename = "minutes")]
+    Minutes,
+    #[serde(rename = "hours")]
+    Hours,
+    #[serde(rename = "days")]
+    Days,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OwnerTeam {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeartbeatData {
+    /// Name of the heartbeat, only part of the body on create.
+    #[serde(skip_serializing)]
+    pub name: String,
+    /// Description of the heartbeat.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Amount of time that Opsgenie waits for a ping before creating an alert.
+    pub interval: u32,
+    pub interval_unit: IntervalUnit,
+    pub enabled: bool,
+    /// Owner team of the heartbeat.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner_team: Option<OwnerTeam>,
+    /// Message of the alert that is created when the heartbeat expires.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alert_message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alert_tags: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alert_priority: Option<Priority>,
+}
+
+impl HeartbeatData {
+    pub fn new(name: String, interval: u32, interval_unit: IntervalUnit) -> HeartbeatData {
+        HeartbeatData {
+            name,
+            description: None,
+            interval,
+            interval_unit,
+            enabled: true,
+            owner_team: None,
+            alert_message: None,
+            alert_tags: None,
+            alert_priority: None,
+        }
+    }
+    pub fn description(mut self, description: String) -> HeartbeatData {
+        self.description = Some(description);
+        self
+    }
+    pub fn enabled(mut self, enabled: bool) -> HeartbeatData {
+        self.enabled = enabled;
+        self
+    }
+    pub fn owner_team(mut self, owner_team: OwnerTeam) -> HeartbeatData {
+        self.owner_team = Some(owner_team);
+        self
+    }
+    pub fn alert_message(mut self, alert_message: String) -> HeartbeatData {
+        self.alert_message = Some(alert_message);
+        self
+    }
+    pub fn alert_tags(mut self, alert_tags: Vec<String>) -> HeartbeatData {
+        self.alert_tags = Some(alert_tags);
+        self
+    }
+    pub fn alert_priority(mut self, alert_priority: Priority) -> HeartbeatData {
+        self.alert_priority = Some(alert_priority);
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeartbeatInfo {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub interval: u32,
+    pub interval_unit: IntervalUnit,
+    pub enabled: bool,
+    pub expired: bool,
+    #[serde(default)]
+    pub owner_team: Option<OwnerTeam>,
+    #[serde(default)]
+    pub alert_message: Option<String>,
+    #[serde(default)]
+    pub alert_tags: Vec<String>,
+    #[serde(default)]
+    pub alert_priority: Option<Priority>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeartbeatStatus {
+    pub name: String,
+    pub enabled: bool,
+    pub expired: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeartbeatList {
+    pub heartbeats: Vec<HeartbeatInfo>,
+}
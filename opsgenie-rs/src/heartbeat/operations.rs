@@ -0,0 +1,118 @@
+use crate::client::OpsgenieClient;
+use crate::errors::OpsgenieError;
+use crate::heartbeat::models::{
+    HeartbeatData, HeartbeatInfo, HeartbeatList, HeartbeatResponse, HeartbeatStatus, PingResponse,
+};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use reqwest::Method;
+use serde::Serialize;
+
+const HEARTBEATS_PATH: &str = "/v2/heartbeats";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Heartbeat;
+
+/// Create body, `name` is only sent on create since update takes it from the path.
+#[derive(Serialize)]
+struct NamedHeartbeatData<'a> {
+    name: &'a str,
+    #[serde(flatten)]
+    data: &'a HeartbeatData,
+}
+
+fn heartbeat_url(client: &OpsgenieClient, name: &str, action: &str) -> String {
+    client.url(&format!(
+        "{}/{}{}",
+        HEARTBEATS_PATH,
+        utf8_percent_encode(name, NON_ALPHANUMERIC),
+        action
+    ))
+}
+
+impl Heartbeat {
+    pub async fn ping(client: &OpsgenieClient, name: &str) -> Result<PingResponse, OpsgenieError> {
+        client
+            .execute(client.request(Method::POST, &heartbeat_url(client, name, "/ping")))
+            .await
+    }
+
+    pub async fn get(client: &OpsgenieClient, name: &str) -> Result<HeartbeatInfo, OpsgenieError> {
+        let resp: HeartbeatResponse<HeartbeatInfo> = client
+            .execute(client.request(Method::GET, &heartbeat_url(client, name, "")))
+            .await?;
+        Ok(resp.data)
+    }
+
+    pub async fn list(client: &OpsgenieClient) -> Result<Vec<HeartbeatInfo>, OpsgenieError> {
+        let resp: HeartbeatResponse<HeartbeatList> = client
+            .execute(client.request(Method::GET, &client.url(HEARTBEATS_PATH)))
+            .await?;
+        Ok(resp.data.heartbeats)
+    }
+
+    pub async fn create(
+        client: &OpsgenieClient,
+        data: HeartbeatData,
+    ) -> Result<HeartbeatStatus, OpsgenieError> {
+        let body = NamedHeartbeatData {
+            name: &data.name,
+            data: &data,
+        };
+        let resp: HeartbeatResponse<HeartbeatStatus> = client
+            .execute(
+                client
+                    .request(Method::POST, &client.url(HEARTBEATS_PATH))
+                    .json(&body),
+            )
+            .await?;
+        Ok(resp.data)
+    }
+
+    pub async fn update(
+        client: &OpsgenieClient,
+        data: HeartbeatData,
+    ) -> Result<HeartbeatStatus, OpsgenieError> {
+        let resp: HeartbeatResponse<HeartbeatStatus> = client
+            .execute(
+                client
+                    .request(Method::PATCH, &heartbeat_url(client, &data.name, ""))
+                    .json(&data),
+            )
+            .await?;
+        Ok(resp.data)
+    }
+
+    pub async fn enable(
+        client: &OpsgenieClient,
+        name: &str,
+    ) -> Result<HeartbeatStatus, OpsgenieError> {
+        let resp: HeartbeatResponse<HeartbeatStatus> = client
+            .execute(client.request(Method::POST, &heartbeat_url(client, name, "/enable")))
+            .await?;
+        Ok(resp.data)
+    }
+
+    pub async fn disable(
+        client: &OpsgenieClient,
+        name: &str,
+    ) -> Result<HeartbeatStatus, OpsgenieError> {
+        let resp: HeartbeatResponse<HeartbeatStatus> = client
+            .execute(client.request(Method::POST, &heartbeat_url(client, name, "/disable")))
+            .await?;
+        Ok(resp.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_is_encoded_in_path() {
+        let client = OpsgenieClient::new("key").unwrap();
+        assert_eq!(
+            heartbeat_url(&client, "ldm pi/1", "/ping"),
+            "https://api.opsgenie.com/v2/heartbeats/ldm%20pi%2F1/ping"
+        );
+    }
+}
@@ -1,4 +1,7 @@
 pub mod alert;
+pub mod client;
+pub mod errors;
+pub mod heartbeat;
 
 #[cfg(test)]
 mod tests {
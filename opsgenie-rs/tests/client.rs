@@ -0,0 +1,217 @@
+use opsgenie_rs::alert::models::{ActionData, AlertData, Identifier};
+use opsgenie_rs::alert::operations::Alert;
+use opsgenie_rs::client::{OpsgenieClient, Region};
+use opsgenie_rs::errors::OpsgenieError;
+use opsgenie_rs::heartbeat::operations::Heartbeat;
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+use std::time::Duration;
+use tiny_http::{Header, Response, Server};
+
+const ACCEPTED: &str = r#"{"result":"Request will be processed","took":0.1,"requestId":"req-1"}"#;
+
+/// Status, headers and body of a canned response.
+type Canned = (u16, Vec<(&'static str, &'static str)>, &'static str);
+
+#[derive(Debug)]
+struct Recorded {
+    method: String,
+    url: String,
+    authorization: Option<String>,
+    body: String,
+}
+
+/// Serves the canned responses in order and records the requests.
+fn stub(responses: Vec<Canned>) -> (String, Receiver<Recorded>) {
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let addr = server.server_addr().to_ip().unwrap();
+    let (tx, rx) = channel();
+    thread::spawn(move || {
+        for (status, headers, body) in responses {
+            let mut request = match server.recv_timeout(Duration::from_secs(5)) {
+                Ok(Some(request)) => request,
+                _ => return,
+            };
+            let mut content = String::new();
+            request.as_reader().read_to_string(&mut content).unwrap();
+            tx.send(Recorded {
+                method: request.method().to_string(),
+                url: request.url().to_string(),
+                authorization: request
+                    .headers()
+                    .iter()
+                    .find(|header| header.field.equiv("Authorization"))
+                    .map(|header| header.value.to_string()),
+                body: content,
+            })
+            .unwrap();
+            let mut response = Response::from_string(body).with_status_code(status);
+            for (field, value) in headers {
+                response = response.with_header(Header::from_bytes(field, value).unwrap());
+            }
+            request.respond(response).unwrap();
+        }
+    });
+    (format!("http://{}", addr), rx)
+}
+
+fn client(base_url: &str) -> OpsgenieClient {
+    OpsgenieClient::builder("test-key")
+        .base_url(base_url)
+        .retries(2, Duration::from_millis(10))
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn create_alert_posts_to_base_url() {
+    let (url, requests) = stub(vec![(202, vec![], ACCEPTED)]);
+    let alert = Alert::create(&client(&url), AlertData::new(String::from("disk full")))
+        .await
+        .unwrap();
+    assert_eq!(alert.request_id(), "req-1");
+    let request = requests.recv().unwrap();
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.url, "/v2/alerts");
+    assert_eq!(request.authorization.as_deref(), Some("GenieKey test-key"));
+    assert!(request.body.contains("disk full"));
+}
+
+#[tokio::test]
+async fn unauthorized_is_auth_error() {
+    let (url, requests) = stub(vec![(
+        401,
+        vec![],
+        r#"{"message":"Key is invalid","took":0.0,"requestId":"r"}"#,
+    )]);
+    let err = Heartbeat::ping(&client(&url), "ldm").await.unwrap_err();
+    match err {
+        OpsgenieError::Auth { status, message } => {
+            assert_eq!(status, 401);
+            assert_eq!(message, "Key is invalid");
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+    assert_eq!(requests.iter().count(), 1);
+}
+
+#[tokio::test]
+async fn missing_alert_is_not_found() {
+    let (url, _requests) = stub(vec![(404, vec![], r#"{"message":"Alert does not exist"}"#)]);
+    let err = Alert::get(&client(&url), &Identifier::Alias(String::from("cpu")))
+        .await
+        .unwrap_err();
+    match err {
+        OpsgenieError::NotFound(message) => assert_eq!(message, "Alert does not exist"),
+        other => panic!("unexpected error {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn rejected_request_is_validation_error() {
+    let (url, _requests) = stub(vec![(422, vec![], "not json")]);
+    let err = Alert::acknowledge(
+        &client(&url),
+        &Identifier::Id(String::from("1")),
+        ActionData::default(),
+    )
+    .await
+    .unwrap_err();
+    match err {
+        OpsgenieError::Validation { status, message } => {
+            assert_eq!(status, 422);
+            assert_eq!(message, "not json");
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn rate_limited_request_is_retried_after_delay() {
+    let (url, requests) = stub(vec![
+        (429, vec![("Retry-After", "0")], ""),
+        (202, vec![], ACCEPTED),
+    ]);
+    let alert = Alert::create(&client(&url), AlertData::new(String::from("cpu")))
+        .await
+        .unwrap();
+    assert_eq!(alert.request_id(), "req-1");
+    assert_eq!(requests.iter().count(), 2);
+}
+
+#[tokio::test]
+async fn long_retry_after_is_returned() {
+    let (url, requests) = stub(vec![(429, vec![("Retry-After", "120")], "")]);
+    let err = Heartbeat::ping(&client(&url), "ldm").await.unwrap_err();
+    match err {
+        OpsgenieError::RateLimited { retry_after } => {
+            assert_eq!(retry_after, Some(Duration::from_secs(120)))
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+    assert_eq!(requests.iter().count(), 1);
+}
+
+#[tokio::test]
+async fn server_errors_are_retried_until_exhausted() {
+    let (url, requests) = stub(vec![
+        (503, vec![], "unavailable"),
+        (500, vec![], "boom"),
+        (502, vec![], "bad gateway"),
+    ]);
+    let err = Heartbeat::ping(&client(&url), "ldm").await.unwrap_err();
+    match err {
+        OpsgenieError::Server { status, .. } => assert_eq!(status, 502),
+        other => panic!("unexpected error {:?}", other),
+    }
+    assert_eq!(requests.iter().count(), 3);
+}
+
+#[tokio::test]
+async fn server_error_then_success() {
+    let (url, requests) = stub(vec![
+        (500, vec![], ""),
+        (
+            202,
+            vec![],
+            r#"{"result":"PONG - Heartbeat received","took":0.0,"requestId":"r"}"#,
+        ),
+    ]);
+    let resp = Heartbeat::ping(&client(&url), "ldm").await.unwrap();
+    assert_eq!(resp.result, "PONG - Heartbeat received");
+    let urls: Vec<String> = requests.iter().map(|request| request.url).collect();
+    assert_eq!(
+        urls,
+        vec!["/v2/heartbeats/ldm/ping", "/v2/heartbeats/ldm/ping"]
+    );
+}
+
+#[tokio::test]
+async fn connection_failure_is_transport_error() {
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+    drop(server);
+    let client = OpsgenieClient::builder("test-key")
+        .base_url(&url)
+        .retries(0, Duration::from_millis(10))
+        .build()
+        .unwrap();
+    match Heartbeat::ping(&client, "ldm").await.unwrap_err() {
+        OpsgenieError::Transport(_) => {}
+        other => panic!("unexpected error {:?}", other),
+    }
+}
+
+#[test]
+fn region_selects_base_url() {
+    let client = OpsgenieClient::builder("key")
+        .region(Region::Eu)
+        .build()
+        .unwrap();
+    assert_eq!(client.base_url(), "https://api.eu.opsgenie.com");
+    let client = OpsgenieClient::builder("key")
+        .base_url("http://localhost:8080/")
+        .build()
+        .unwrap();
+    assert_eq!(client.url("/v2/alerts"), "http://localhost:8080/v2/alerts");
+}